@@ -45,8 +45,7 @@ fn main() {
     ))
     .add_observer(dynamic_shards)
     .add_observer(hide_glass)
-    .add_systems(Startup, setup_scene)
-    .add_systems(FixedUpdate, shatter_on_contact);
+    .add_systems(Startup, setup_scene);
 
     app.run();
 }
@@ -102,7 +101,11 @@ fn setup_scene(
         },
         MeshMaterial3d(glass_material.clone()),
         RigidBody::Static,
-        CollisionEventsEnabled,
+        // sprinting into the glass breaks it, walking into it does not
+        ShatterOnImpact {
+            min_impulse: 0.0,
+            min_relative_speed: 7.0,
+        },
     ));
 }
 
@@ -115,28 +118,3 @@ fn dynamic_shards(trigger: Trigger<OnAdd, Shard>, mut commands: Commands) {
 fn hide_glass(trigger: Trigger<OnAdd, Shattered>, mut commands: Commands) {
     commands.entity(trigger.target()).insert(Visibility::Hidden);
 }
-
-// function to shatter glass when player collides with it
-// this is very ugly, consider using https://idanarye.github.io/bevy-tnua/avian3d/collision/contact_types/struct.Collisions.html
-fn shatter_on_contact(
-    mut collision_event_reader: EventReader<CollisionStarted>,
-    player: Single<Entity, With<Player>>,
-    glasses: Populated<Entity, With<Glass>>,
-    mut commands: Commands,
-) {
-    let player_entity = player.into_inner();
-    for CollisionStarted(entity1, entity2) in collision_event_reader.read() {
-        let entity1 = *entity1;
-        let entity2 = *entity2;
-
-        if entity1 == player_entity {
-            if glasses.contains(entity2) {
-                commands.entity(entity2).insert(Shattered);
-            }
-        } else if entity2 == player_entity {
-            if glasses.contains(entity1) {
-                commands.entity(entity1).insert(Shattered);
-            }
-        }
-    }
-}
@@ -61,7 +61,7 @@ fn main() {
     .add_observer(static_shards)
     .add_observer(hide_glass)
     .add_systems(Startup, (setup_scene, setup_camera))
-    .add_systems(Update, (click_shatter, drop_shards));
+    .add_systems(Update, click_shatter);
 
     app.run();
 }
@@ -118,6 +118,14 @@ fn setup_scene(
         MeshMaterial3d(glass_material.clone()),
         RigidBody::Static,
         CollisionEventsEnabled,
+        // shards near the hit fall in waves, starting at the hit
+        ShardRelease {
+            speed: 4.0,
+            jitter: 0.2,
+            max_radius: 2.0,
+        },
+        // shards that end up hanging from nothing fall too
+        StructuralSupport,
     ));
 }
 
@@ -177,31 +185,6 @@ fn static_shards(trigger: Trigger<OnAdd, Shard>, mut commands: Commands) {
         .insert(GravityScale(2.0));
 }
 
-// helper to store where the glass was hit, relative to the bottom left of the glass
-#[derive(Component)]
-pub struct GlassHitPoint(Vec2);
-
-// make shards fall if close to the hit point
-// this could be improved to make all shards fall at different times, depending on how far
-// away they are, making a radial pattern
-// but this would make the example even more complex than it already is
-fn drop_shards(
-    shattered_glasses: Populated<(&GlassHitPoint, &Shards), With<Glass>>,
-    mut shards: Query<(&Shard, &mut RigidBody)>,
-) {
-    for (hit, shard_children) in shattered_glasses.iter() {
-        for shard in shard_children.iter() {
-            // the shard will obviously belong to the shards query
-            let (shard_info, mut shard_body) = shards.get_mut(shard).unwrap();
-
-            let distance = shard_info.pos.distance(hit.0);
-            if distance < 2.0 {
-                *shard_body = RigidBody::Dynamic;
-            }
-        }
-    }
-}
-
 // hook to hide the glass when it is shattered
 fn hide_glass(trigger: Trigger<OnAdd, Shattered>, mut commands: Commands) {
     // remove the rigid body too otherwise the debug physics plugin makes this visible again for some reason
@@ -238,10 +221,18 @@ fn click_shatter(
 
                 let relative_break_pos = glass.project_to_glass(glass_transf, hit_position);
 
-                commands
-                    .entity(glass_entity)
-                    .insert(Shattered)
-                    .insert(GlassHitPoint(relative_break_pos));
+                // the impact is also used by ShardRelease to know where the glass was hit,
+                // and the energy sprays the shards away from the camera like a bullet would
+                commands.entity(glass_entity).insert((
+                    Shattered,
+                    ShatterImpact {
+                        point: relative_break_pos,
+                        radius: 1.5,
+                        falloff: 1.5,
+                        direction: cam_forward.as_vec3(),
+                        energy: 2.0,
+                    },
+                ));
             }
         }
     }
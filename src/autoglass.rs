@@ -1,4 +1,5 @@
-use bevy::prelude::*;
+use avian3d::prelude::*;
+use bevy::{platform::collections::HashMap, prelude::*};
 
 use crate::*;
 
@@ -10,14 +11,16 @@ use crate::*;
 /// - [`AutoGlass`]
 ///
 /// Added components:
-/// - [`Glass`]
-/// - [`Transform`], with the correct scale
-/// - [`Mesh3d`], as a cuboid
-/// - [`Collider`], as a cuboid
+/// - [`Glass`], with its [`Glass::size`] set to the width, height and thickness
+/// - [`Transform`], with the translation and rotation
+/// - [`Mesh3d`], from [`Glass::mesh`]
+/// - [`Collider`], from [`Glass::collider`]
+///
+/// Flat rectangles of the same size share the same mesh and collider.
+/// If the mesh can't be built, the glass is still added, without a mesh or a collider.
 ///
 /// Note: no material or rigid body are added.
-/// You can completely ignore this and do things manually for more control. Keep in mind this function
-/// uses meshes and colliders of size 1x1x1, being only resized by their transform's scale
+/// You can completely ignore this and do things manually for more control
 #[derive(Component, Debug)]
 pub struct AutoGlass {
     pub glass: Glass,
@@ -28,31 +31,53 @@ pub struct AutoGlass {
     pub rotation: Quat,
 }
 
+/// Meshes and colliders of the flat rectangular [`AutoGlass`]es, by size, so glasses of the same size share them.
+/// They are never removed, so every distinct size keeps one mesh alive even once all of its glasses are despawned
+#[derive(Resource, Default)]
+pub(super) struct RectangleGlasses(HashMap<[u32; 3], (Handle<Mesh>, Collider)>);
+
 // The entire thing is cursed but works
 // FIX: can't I read the AutoGlass struct straigt from the trigger???????????? that way I don't need the query or the unwrap
 /// Hook to add [`AutoGlass`] functionality when it is added to an entity
 pub(super) fn autoglass_hook(
     trigger: Trigger<OnAdd, AutoGlass>,
     mut commands: Commands,
-    collider: Res<GlassCollider>,
-    mesh: Res<GlassMesh>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut rectangles: ResMut<RectangleGlasses>,
     autoglasses: Populated<&AutoGlass>,
 ) {
     let entity = trigger.target();
     let mut entitycmd = commands.entity(entity);
 
     let ag = autoglasses.get(entity).unwrap();
-    let glass = &ag.glass;
-
-    entitycmd.insert((
-        Transform {
-            translation: ag.translation,
-            rotation: ag.rotation,
-            scale: Vec3::new(ag.width, ag.height, ag.thickness),
-        },
-        Mesh3d(mesh.0.clone()),
-        collider.0.clone(), // TODO: test if this is faster than recomputing the collider
-        glass.clone(),
-    ));
+    let glass = ag
+        .glass
+        .clone()
+        .with_size(Vec3::new(ag.width, ag.height, ag.thickness));
+
+    let transform = Transform::from_translation(ag.translation).with_rotation(ag.rotation);
+
+    // rectangles can share the same mesh and collider, other shapes get their own
+    let key = (glass.is_rectangle() && glass.curvature.is_none())
+        .then(|| glass.size.to_array().map(f32::to_bits));
+    let shared = key.and_then(|key| rectangles.0.get(&key).cloned());
+    let built = shared.or_else(|| {
+        let (mesh, collider) = (glass.mesh()?, glass.collider()?);
+        let built = (meshes.add(mesh), collider);
+        if let Some(key) = key {
+            rectangles.0.insert(key, built.clone());
+        }
+        Some(built)
+    });
+
+    match built {
+        Some((mesh, collider)) => {
+            entitycmd.insert((transform, Mesh3d(mesh), collider, glass));
+        }
+        None => {
+            warn!("Could not build a mesh for the glass shape, is it too small?");
+            entitycmd.insert((transform, glass));
+        }
+    }
     entitycmd.remove::<AutoGlass>();
 }
@@ -0,0 +1,304 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+};
+
+use crate::{geometry::*, *};
+
+/// Makes a glass take damage before shattering. Hit it by triggering a [`GlassHit`] on it.
+///
+/// Hits add cracks to the glass (see [`Cracked`]) until the accumulated damage reaches the limit,
+/// at which point the glass is [`Shattered`] around the last hit. If the glass already has a [`ShatterImpact`], only its point is moved to the hit.
+/// The cracks are reused when shattering, so the shards follow the cracks the player saw.
+#[derive(Component, Clone, Debug)]
+pub struct GlassHealth {
+    /// Once the damage reaches this value, the glass shatters
+    pub limit: f32,
+    /// Damage accumulated so far
+    pub damage: f32,
+    /// Length of the cracks created by a hit that deals `limit` damage. Weaker hits create shorter cracks
+    pub crack_length: f32,
+    /// Width of the crack lines
+    pub crack_width: f32,
+}
+
+impl GlassHealth {
+    /// Creates a glass that shatters after taking `limit` damage
+    pub fn new(limit: f32) -> Self {
+        Self {
+            limit,
+            damage: 0.0,
+            crack_length: 1.0,
+            crack_width: 0.01,
+        }
+    }
+}
+
+/// Trigger this on an entity with [`Glass`] and [`GlassHealth`] to damage it,
+/// for example `commands.trigger_targets(GlassHit { point, damage: 10.0 }, glass)`
+#[derive(Event, Clone, Debug)]
+pub struct GlassHit {
+    /// Position of the hit in the glass, relative to the bottom left point. See [`Glass::project_to_glass`]
+    pub point: Vec2,
+    /// How much damage to add to [`GlassHealth::damage`]
+    pub damage: f32,
+}
+
+/// Added to a glass the first time it is hit without shattering. Stores all of its cracks.
+///
+/// The cracks are drawn by child entities with the [`Crack`] component, one for each hit.
+/// When the glass shatters, [`FractureMode::Voronoi`] and [`FractureMode::Triangles`] add `seeds` to the cell points,
+/// which makes the edges of the shards follow the cracks. The other modes don't use cell points,
+/// so their cells are cut along the `lines` instead, from one edge of the cell to the other
+#[derive(Component, Clone, Debug, Default)]
+pub struct Cracked {
+    /// Every crack segment, relative to the bottom left of the glass
+    pub lines: Vec<[Vec2; 2]>,
+    /// Cell points placed in pairs on both sides of each crack segment
+    pub seeds: Vec<Vec2>,
+}
+
+impl Cracked {
+    /// Adds a crack segment, along with the seeds that make the shards break along it
+    pub fn add_line(&mut self, start: Vec2, end: Vec2) {
+        // the edge between the cells of two points is halfway between them,
+        // so mirroring two points across the segment makes the edge go along the segment
+        let offset = (end - start).perp() / 2.0;
+        let middle = (start + end) / 2.0;
+
+        self.lines.push([start, end]);
+        self.seeds.push(middle + offset);
+        self.seeds.push(middle - offset);
+    }
+
+    /// Cuts every cell that a crack goes through in two, along the line of the crack.
+    /// Used by the fracture modes that don't use the seeds
+    pub(crate) fn split(&self, mut cells: Vec<Cell>, epsilon: f32) -> Vec<Cell> {
+        for &line in self.lines.iter() {
+            cells = cells
+                .into_iter()
+                .flat_map(|cell| {
+                    if !cell
+                        .polygons
+                        .iter()
+                        .any(|polygon| crosses(polygon, line, epsilon))
+                    {
+                        return vec![cell];
+                    }
+
+                    let [start, end] = line;
+                    let sides = [half_plane(start, end - start), half_plane(end, start - end)];
+                    sides
+                        .iter()
+                        .filter_map(|side| {
+                            let polygons: Vec<Vec<Vec2>> = cell
+                                .polygons
+                                .iter()
+                                .map(|polygon| clip_polygon(polygon, side, epsilon))
+                                .filter(|polygon| {
+                                    polygon.len() >= 3 && polygon_area(polygon) > epsilon * epsilon
+                                })
+                                .collect();
+
+                            (!polygons.is_empty()).then(|| Cell {
+                                pos: polygons_centroid(&polygons),
+                                polygons,
+                            })
+                        })
+                        .collect()
+                })
+                .collect();
+        }
+
+        cells
+    }
+}
+
+/// Whether a crack goes through the inside of a convex, counter-clockwise polygon (Cyrus-Beck clipping)
+fn crosses(polygon: &[Vec2], [start, end]: [Vec2; 2], epsilon: f32) -> bool {
+    let direction = end - start;
+    let (mut enter, mut exit) = (0.0_f32, 1.0_f32);
+
+    for (i, &a) in polygon.iter().enumerate() {
+        let edge = polygon[(i + 1) % polygon.len()] - a;
+        let towards = edge.perp_dot(direction);
+        let distance = edge.perp_dot(start - a);
+
+        if towards.abs() <= f32::EPSILON {
+            // parallel to the edge, so it is either always inside of it or never
+            if distance < 0.0 {
+                return false;
+            }
+            continue;
+        }
+
+        let t = -distance / towards;
+        if towards > 0.0 {
+            enter = enter.max(t);
+        } else {
+            exit = exit.min(t);
+        }
+    }
+
+    (exit - enter) * direction.length() > epsilon
+}
+
+/// A huge counter-clockwise square covering everything to the left of the line that goes through `point` along `direction`
+fn half_plane(point: Vec2, direction: Vec2) -> [Vec2; 4] {
+    const EXTENT: f32 = 1.0e4;
+
+    let along = direction.normalize_or_zero() * EXTENT;
+    let left = along.perp();
+    [
+        point - along,
+        point + along,
+        point + along + left,
+        point - along + left,
+    ]
+}
+
+/// Marks the entities that draw the cracks of a [`Cracked`] glass. They are children of the glass and use its material.
+/// Use a hook on this component to customize them, for example by giving them another material
+#[derive(Component, Debug)]
+pub struct Crack;
+
+/// A glass that can take hits, with the cracks and the impact it already has, if any
+type HitGlass<'a> = (
+    &'a Glass,
+    &'a mut GlassHealth,
+    &'a MeshMaterial3d<StandardMaterial>,
+    Option<&'a mut Cracked>,
+    Option<&'a ShatterImpact>,
+    Option<&'a mut ShatterRng>,
+);
+
+/// Observer that applies a [`GlassHit`] to a glass, cracking or shattering it
+pub(super) fn glass_hit_observer(
+    trigger: Trigger<GlassHit>,
+    mut glasses: Query<HitGlass, Without<Shattered>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let entity = trigger.target();
+    let hit = trigger.event();
+
+    let Ok((glass, mut health, material, cracked, impact, rng)) = glasses.get_mut(entity) else {
+        return;
+    };
+
+    health.damage += hit.damage;
+
+    if health.damage >= health.limit {
+        // keeps the radius, falloff and energy the glass already has, if any
+        let impact = ShatterImpact {
+            point: hit.point,
+            ..impact
+                .cloned()
+                .unwrap_or_else(|| ShatterImpact::new(hit.point))
+        };
+        commands.entity(entity).insert((impact, Shattered));
+        return;
+    }
+
+    let mut rng = ShatterRng::or_random(rng);
+
+    let size = glass.size;
+    let length = health.crack_length * (hit.damage / health.limit).clamp(0.2, 1.0);
+
+    let mut new_cracks = Cracked::default();
+    for [start, end] in crack_lines(hit.point, length, size.xy(), &mut rng) {
+        new_cracks.add_line(start, end);
+    }
+
+    let mut mesh = crack_mesh(&new_cracks.lines, health.crack_width, size.z);
+    if let Some(curvature) = &glass.curvature {
+        mesh = curvature.bend_mesh(mesh, size.xy());
+    }
+
+    commands.spawn((
+        bottom_left(size),
+        Mesh3d(meshes.add(mesh)),
+        material.clone(),
+        ChildOf(entity),
+        Crack,
+    ));
+
+    match cracked {
+        Some(mut cracked) => {
+            cracked.lines.append(&mut new_cracks.lines);
+            cracked.seeds.append(&mut new_cracks.seeds);
+        }
+        None => {
+            commands.entity(entity).insert(new_cracks);
+        }
+    }
+}
+
+/// A few cracks going out from `point` in random directions, each made of a few wobbly segments
+fn crack_lines(point: Vec2, length: f32, size: Vec2, rng: &mut ShatterRng) -> Vec<[Vec2; 2]> {
+    const SEGMENTS: u32 = 4;
+    const WOBBLE: f32 = 0.4;
+
+    let inside = |point: Vec2| point.cmpge(Vec2::ZERO).all() && point.cmple(size).all();
+
+    let mut lines = Vec::new();
+    let cracks = rng.u32(3..=6);
+
+    for _ in 0..cracks {
+        let mut angle = rng.f32() * core::f32::consts::TAU;
+        let mut start = point;
+
+        for _ in 0..SEGMENTS {
+            angle += (rng.f32() - 0.5) * WOBBLE;
+            let segment_length = length / SEGMENTS as f32 * (0.5 + rng.f32());
+            let (sin, cos) = ops::sin_cos(angle);
+            let end = start + Vec2::new(cos, sin) * segment_length;
+
+            if !inside(end) {
+                break;
+            }
+
+            lines.push([start, end]);
+            start = end;
+        }
+    }
+
+    lines
+}
+
+/// Thin quads along each line, slightly in front of the top face (z = 0) and behind the bottom face (z = -thickness)
+fn crack_mesh(lines: &[[Vec2; 2]], width: f32, thickness: f32) -> Mesh {
+    // keeps the cracks from z-fighting with the glass
+    const OFFSET: f32 = 0.001;
+
+    let mut positions: Vec<Vec3> = Vec::with_capacity(lines.len() * 8);
+    let mut normals: Vec<Vec3> = Vec::with_capacity(lines.len() * 8);
+    let mut indices: Vec<u32> = Vec::with_capacity(lines.len() * 12);
+
+    for &[start, end] in lines {
+        let side = (end - start).normalize_or_zero().perp() * width / 2.0;
+        let quad = [start - side, end - side, end + side, start + side];
+
+        for (z, normal) in [(OFFSET, Vec3::Z), (-thickness - OFFSET, Vec3::NEG_Z)] {
+            let first = positions.len() as u32;
+            positions.extend(quad.iter().map(|point| point.extend(z)));
+            normals.extend([normal; 4]);
+
+            // counter clockwise when looking at the face from the outside
+            if normal == Vec3::Z {
+                indices.extend([first, first + 1, first + 2, first + 2, first + 3, first]);
+            } else {
+                indices.extend([first, first + 2, first + 1, first + 2, first, first + 3]);
+            }
+        }
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_indices(Indices::U32(indices))
+}
@@ -0,0 +1,199 @@
+use bevy::{prelude::*, render::mesh::VertexAttributeValues};
+
+use crate::{geometry::*, *};
+
+/// Bends a [`Glass`] into a curved pane, such as a windshield, the shop front of a round building or a lamp.
+/// Set it with [`Glass::with_curvature`].
+///
+/// The flat glass is wrapped onto the curve without stretching it, so the width and height of the glass are measured along the curve.
+/// The center of the curve is behind the glass (towards -Z), so the middle of the glass bulges out towards +Z. A negative radius bends it the other way.
+/// The radius must not be 0.
+///
+/// Shards are cut from the flat glass (so [`Shard::polygons`] are flat) and then bent, along with the mesh and collider of the glass itself
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GlassCurvature {
+    /// Bent around a vertical axis, so the height of the glass stays straight
+    Cylindrical {
+        /// Radius of the cylinder
+        radius: f32,
+    },
+    /// Bent in every direction, around a sphere
+    Spherical {
+        /// Radius of the sphere
+        radius: f32,
+    },
+}
+
+impl GlassCurvature {
+    /// How many straight segments would make a full circle. The glass is split into pieces this small so that it follows the curve
+    pub const SEGMENTS_PER_TURN: u32 = 64;
+
+    /// Radius of the curve
+    pub fn radius(&self) -> f32 {
+        match *self {
+            GlassCurvature::Cylindrical { radius } | GlassCurvature::Spherical { radius } => radius,
+        }
+    }
+
+    /// Moves a point of the flat glass onto the curve. Both are relative to the bottom left of a glass of the given width and height,
+    /// with the top face at z = 0
+    pub fn bend(&self, point: Vec3, size: Vec2) -> Vec3 {
+        let radius = self.radius();
+
+        // the distance from the middle of the glass becomes an angle around the center of the curve
+        let (center, offset) = match self {
+            GlassCurvature::Cylindrical { .. } => (
+                Vec3::new(size.x / 2.0, point.y, -radius),
+                Vec2::new(point.x - size.x / 2.0, 0.0),
+            ),
+            GlassCurvature::Spherical { .. } => {
+                ((size / 2.0).extend(-radius), point.xy() - size / 2.0)
+            }
+        };
+
+        let (sin, cos) = ops::sin_cos(offset.length() / radius);
+        let direction = offset.normalize_or_zero() * sin;
+        center + (radius + point.z) * direction.extend(cos)
+    }
+
+    /// Moves a point near the curve back onto the flat glass, the opposite of [`GlassCurvature::bend`]
+    pub fn unbend(&self, point: Vec3, size: Vec2) -> Vec3 {
+        let radius = self.radius();
+
+        let (center, horizontal) = match self {
+            GlassCurvature::Cylindrical { .. } => {
+                let center = Vec3::new(size.x / 2.0, point.y, -radius);
+                (center, Vec2::new(point.x - center.x, 0.0))
+            }
+            GlassCurvature::Spherical { .. } => {
+                let center = (size / 2.0).extend(-radius);
+                (center, point.xy() - center.xy())
+            }
+        };
+        let depth = point.z - center.z;
+
+        // the angle around the center of the curve becomes a distance from the middle of the glass again
+        let distance = Vec2::new(horizontal.length(), depth).length() * radius.signum();
+        let angle = ops::atan2(horizontal.length(), depth * radius.signum());
+        let offset = horizontal.normalize_or_zero() * angle * radius.abs();
+
+        match self {
+            GlassCurvature::Cylindrical { .. } => {
+                Vec3::new(center.x + offset.x, point.y, distance - radius)
+            }
+            GlassCurvature::Spherical { .. } => (center.xy() + offset).extend(distance - radius),
+        }
+    }
+
+    /// Splits convex polygons of the flat glass into smaller ones along a grid, so that once bent they follow the curve
+    pub(crate) fn subdivide(&self, polygons: &[Vec<Vec2>]) -> Vec<Vec<Vec2>> {
+        const EPSILON: f32 = 0.001;
+
+        let step = self.radius().abs() * core::f32::consts::TAU / Self::SEGMENTS_PER_TURN as f32;
+
+        let mut pieces: Vec<Vec<Vec2>> = Vec::with_capacity(polygons.len());
+        for polygon in polygons {
+            let min = polygon.iter().copied().fold(Vec2::MAX, Vec2::min);
+            let max = polygon.iter().copied().fold(Vec2::MIN, Vec2::max);
+
+            // cylinders are only bent along the width, so there is no need to split the height
+            let (first, last) = match self {
+                GlassCurvature::Cylindrical { .. } => (
+                    IVec2::new((min.x / step).floor() as i32, 0),
+                    IVec2::new((max.x / step).ceil() as i32, 1),
+                ),
+                GlassCurvature::Spherical { .. } => (
+                    (min / step).floor().as_ivec2(),
+                    (max / step).ceil().as_ivec2(),
+                ),
+            };
+
+            for y in first.y..last.y {
+                for x in first.x..last.x {
+                    let (cell_min, cell_max) = match self {
+                        GlassCurvature::Cylindrical { .. } => (
+                            Vec2::new(x as f32 * step, min.y),
+                            Vec2::new((x + 1) as f32 * step, max.y),
+                        ),
+                        GlassCurvature::Spherical { .. } => (
+                            IVec2::new(x, y).as_vec2() * step,
+                            (IVec2::new(x, y) + 1).as_vec2() * step,
+                        ),
+                    };
+
+                    let cell = rectangle(cell_max - cell_min).map(|corner| corner + cell_min);
+                    let piece = clip_polygon(polygon, &cell, EPSILON);
+                    if piece.len() >= 3 && polygon_area(&piece).abs() > EPSILON * EPSILON {
+                        pieces.push(piece);
+                    }
+                }
+            }
+        }
+
+        pieces
+    }
+
+    /// Bends every vertex of a mesh of the flat glass, recomputing its normals
+    pub(crate) fn bend_mesh(&self, mut mesh: Mesh, size: Vec2) -> Mesh {
+        if let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+        {
+            for position in positions.iter_mut() {
+                *position = self.bend(Vec3::from(*position), size).to_array();
+            }
+        }
+
+        // the normals changed, and flat normals need every triangle to have its own vertices
+        if mesh.indices().is_some() {
+            mesh.duplicate_vertices();
+        }
+        mesh.compute_flat_normals();
+        mesh
+    }
+}
+
+/// Extrudes polygons of the flat glass like [`extrude_polygons`], bending the mesh if the glass is curved.
+/// `size` is the width, height and thickness of the glass
+pub(crate) fn extrude_glass(
+    polygons: &[Vec<Vec2>],
+    size: Vec3,
+    curvature: Option<&GlassCurvature>,
+) -> Option<Mesh> {
+    match curvature {
+        None => extrude_polygons(polygons, size.z),
+        Some(curvature) => extrude_polygons(&curvature.subdivide(polygons), size.z)
+            .map(|mesh| curvature.bend_mesh(mesh, size.xy())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn unbend_undoes_bend() {
+        let size = Vec2::new(3.0, 2.0);
+        let curvatures = [
+            GlassCurvature::Cylindrical { radius: 1.0 },
+            GlassCurvature::Cylindrical { radius: -2.0 },
+            GlassCurvature::Spherical { radius: 1.5 },
+            GlassCurvature::Spherical { radius: -3.0 },
+        ];
+        let points = [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.5, 1.0, -0.05),
+            Vec3::new(3.0, 0.3, -0.1),
+            Vec3::new(0.4, 2.0, -0.02),
+        ];
+
+        for curvature in curvatures {
+            for point in points {
+                let unbent = curvature.unbend(curvature.bend(point, size), size);
+                assert!(
+                    unbent.distance(point) < 1e-4,
+                    "{curvature:?} moved {point} to {unbent}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    platform::collections::HashMap,
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+};
+use voronator::{delaunator::*, VoronoiDiagram};
+
+use crate::{geometry::*, spiderweb::max_radius, *};
+
+/// How the glass is split into shards when it is shattered.
+/// Every mode creates flat 2D cells that are then extruded into shards the same way, so they all get the same meshes, colliders and [`Shard`] components.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FractureMode {
+    /// Each cell point becomes a polygonal shard, using a voronoi diagram
+    #[default]
+    Voronoi,
+    /// The cell points (and the corners of the glass) are joined into triangles, using a delaunay triangulation.
+    /// This creates about twice as many shards as there are cell points, and they are much sharper
+    Triangles,
+    /// Wedges that go out from the [`ShatterImpact`] (or the center of the glass if there is none),
+    /// cut into pieces by evenly spaced rings. The cell points are not used
+    Wedges {
+        /// Number of cracks that go out from the impact, must be at least 3
+        spokes: u32,
+        /// Number of rings that cut the spokes
+        rings: u32,
+    },
+    /// Like [`FractureMode::Wedges`], but with rings that get further apart and random jitter, like real annealed glass.
+    /// Centered at the [`ShatterImpact`] (or the center of the glass if there is none). The cell points are not used
+    Spiderweb(Spiderweb),
+}
+
+/// A flat piece of the glass, before it is extruded
+pub(crate) struct Cell {
+    /// See [`Shard::pos`]
+    pub pos: Vec2,
+    /// Convex polygons that make up the cell. There is only more than one when the cell was clipped against an [`IntactGlass`]
+    pub polygons: Vec<Vec<Vec2>>,
+}
+
+impl FractureMode {
+    /// Whether this mode needs the cell points from the [`SeedGenerator`]
+    pub(crate) fn uses_seeds(&self) -> bool {
+        !matches!(
+            self,
+            FractureMode::Wedges { .. } | FractureMode::Spiderweb(_)
+        )
+    }
+
+    /// Splits a glass of the given width and height into cells
+    pub(crate) fn cells(
+        &self,
+        seeds: &[Vec2],
+        size: Vec2,
+        impact: Option<&ShatterImpact>,
+        rng: &mut ShatterRng,
+        epsilon: f32,
+    ) -> Vec<Cell> {
+        match *self {
+            FractureMode::Voronoi => voronoi_cells(seeds, size),
+            FractureMode::Triangles => triangle_cells(seeds, size, epsilon),
+            FractureMode::Wedges { spokes, rings } => {
+                let center = impact.map_or(size / 2.0, |impact| impact.point);
+                wedge_cells(center, spokes, rings, size, rng, epsilon)
+            }
+            FractureMode::Spiderweb(spiderweb) => {
+                let center = impact.map_or(size / 2.0, |impact| impact.point);
+                spiderweb.cells(center, size, rng, epsilon)
+            }
+        }
+    }
+}
+
+fn voronoi_cells(seeds: &[Vec2], size: Vec2) -> Vec<Cell> {
+    let points: Vec<(f64, f64)> = seeds
+        .iter()
+        .map(|seed| (seed.x as f64, seed.y as f64))
+        .collect();
+
+    // voronator needs at least 3 points that are not all in a line
+    let Some(voronoi_diagram) =
+        VoronoiDiagram::<Point>::from_tuple(&(0., 0.), &(size.x as f64, size.y as f64), &points)
+    else {
+        warn!("Failed to generate the voronoi diagram of the glass");
+        return Vec::new();
+    };
+
+    voronoi_diagram
+        .cells()
+        .iter()
+        .zip(seeds)
+        .map(|(cell, &seed)| Cell {
+            pos: seed,
+            polygons: vec![cell
+                .points()
+                .iter()
+                .map(|point| Vec2::new(point.x as f32, point.y as f32))
+                .collect()],
+        })
+        .collect()
+}
+
+fn triangle_cells(seeds: &[Vec2], size: Vec2, epsilon: f32) -> Vec<Cell> {
+    // the corners are needed for the triangles to cover the entire glass
+    let mut points = SeedSet::new(size, epsilon);
+    for corner in rectangle(size) {
+        points.insert(corner);
+    }
+    for &seed in seeds {
+        points.insert(seed);
+    }
+
+    let points: Vec<Point> = points
+        .points()
+        .iter()
+        .map(|point| Point {
+            x: point.x as f64,
+            y: point.y as f64,
+        })
+        .collect();
+
+    let Some(delaunay) = triangulate::<Point>(&points) else {
+        warn!("Failed to triangulate the glass");
+        return Vec::new();
+    };
+
+    delaunay
+        .triangles
+        .chunks(3)
+        .map(|triangle| {
+            let polygon: Vec<Vec2> = triangle
+                .iter()
+                .map(|&index| Vec2::new(points[index].x as f32, points[index].y as f32))
+                .collect();
+
+            Cell {
+                pos: polygon_centroid(&polygon),
+                polygons: vec![polygon],
+            }
+        })
+        .collect()
+}
+
+/// Evenly spaced rings, with no randomness other than the rotation of the whole pattern
+fn wedge_cells(
+    center: Vec2,
+    spokes: u32,
+    rings: u32,
+    size: Vec2,
+    rng: &mut ShatterRng,
+    epsilon: f32,
+) -> Vec<Cell> {
+    let rings = rings.max(1);
+    let max_radius = max_radius(center, size, epsilon);
+    let radii: Vec<f32> = (1..=rings)
+        .map(|ring| max_radius * ring as f32 / rings as f32)
+        .collect();
+
+    Spiderweb {
+        spokes,
+        angular_jitter: 0.0,
+        ring_jitter: 0.0,
+        ..default()
+    }
+    .cells_with_radii(center, &radii, size, rng, epsilon)
+}
+
+/// Extrudes flat convex polygons into a single prism of the given thickness, returning its mesh.
+/// The polygons are the top face (z = 0) and the bottom face is at z = -thickness.
+/// Polygons that share edges are welded together, so the sides are only created around the outline of all of them.
+/// Returns `None` if none of the polygons could be triangulated
+// TODO: consider generating the normals myself
+// TODO: extruding vertices was way harder than I expected, I have no idea thy I use negative values like -width and -thickness,
+// if it works it works. try to replace this with some lib that can extrude meshes in the future, I couldn't find anything decent and lightweight
+pub(crate) fn extrude_polygons(polygons: &[Vec<Vec2>], thickness: f32) -> Option<Mesh> {
+    // points closer than this are considered the same vertex
+    const WELD_DISTANCE: f32 = 0.0001;
+
+    // vertices shared by all the polygons, so that the edges between them cancel out below
+    let mut points: Vec<Vec2> = Vec::new();
+    let mut point_indices: HashMap<IVec2, usize> = HashMap::new();
+    let mut triangles: Vec<usize> = Vec::new();
+
+    for polygon in polygons {
+        let delaunay_points: Vec<Point> = polygon
+            .iter()
+            .map(|point| Point {
+                x: point.x as f64,
+                y: point.y as f64,
+            })
+            .collect();
+
+        // if the cell has less than 3 points it can't be triangulated, this is extremely rare, have to find out why it happens
+        let Some(delaunay) = triangulate::<Point>(&delaunay_points) else {
+            continue;
+        };
+
+        let indices: Vec<usize> = polygon
+            .iter()
+            .map(|&point| {
+                let key = (point / WELD_DISTANCE).round().as_ivec2();
+                *point_indices.entry(key).or_insert_with(|| {
+                    points.push(point);
+                    points.len() - 1
+                })
+            })
+            .collect();
+
+        triangles.extend(delaunay.triangles.iter().map(|&index| indices[index]));
+    }
+
+    if triangles.is_empty() {
+        return None;
+    }
+
+    // Original vertices are used as the top (z = 0)
+    let mut verts: Vec<Vec3> = points.iter().map(|point| point.extend(0.0)).collect();
+    let n = verts.len();
+
+    // Extruded vertices as the bottom (z = -thickness)
+    let mut top_verts: Vec<Vec3> = points
+        .iter()
+        .map(|point| point.extend(-thickness))
+        .collect();
+    verts.append(&mut top_verts);
+
+    // now we have to make edges to join the bottom and top vertices.
+    // from here on this was mostly made by grok as I couldn't find any resources on this, and
+    // making the triangles have the exact order you need them to have is hard
+    let mut edge_count: HashMap<(usize, usize), i32> = HashMap::new();
+    for triangle in triangles.chunks(3) {
+        let edges = [
+            (triangle[0], triangle[1]),
+            (triangle[1], triangle[2]),
+            (triangle[2], triangle[0]),
+        ];
+        for &(a, b) in edges.iter() {
+            *edge_count.entry((a, b)).or_insert(0) += 1;
+            *edge_count.entry((b, a)).or_insert(0) -= 1;
+        }
+    }
+
+    // Only keep edges that appear once (boundary edges)
+    let boundary_edges: Vec<(usize, usize)> = edge_count
+        .iter()
+        .filter(|&(&(_, _), &count)| count == 1)
+        .map(|(&(a, b), _)| (a, b))
+        .collect();
+
+    let mut indices: Vec<u32> = Vec::new();
+
+    // Bottom faces (reversed for outward facing)
+    for triangle in triangles.chunks(3) {
+        indices.extend_from_slice(&[triangle[2] as u32, triangle[1] as u32, triangle[0] as u32]);
+    }
+
+    // Top faces
+    for triangle in triangles.chunks(3) {
+        indices.extend_from_slice(&[
+            (triangle[0] + n) as u32,
+            (triangle[1] + n) as u32,
+            (triangle[2] + n) as u32,
+        ]);
+    }
+
+    // Side faces with proper winding
+    // TODO: calculate normals here??
+    for &(a, b) in boundary_edges.iter() {
+        indices.extend_from_slice(&[
+            a as u32,
+            b as u32,
+            (b + n) as u32,
+            (b + n) as u32,
+            (a + n) as u32,
+            a as u32,
+        ]);
+    }
+
+    // Create the mesh
+    // I assume I will never need the mesh on the CPU again
+    let mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, verts)
+    // .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_indices(Indices::U32(indices));
+
+    // add the normals. this is VERY inneficient but whatever, had many issues doing it manually
+    Some(mesh.with_duplicated_vertices().with_computed_flat_normals())
+}
@@ -0,0 +1,609 @@
+// Small 2D polygon helpers used when fracturing glass. Polygons are lists of points in counter-clockwise order
+
+use bevy::{platform::collections::HashMap, prelude::*};
+
+/// Clips `subject` against the convex polygon `clip` (Sutherland-Hodgman), returning the part of `subject` inside of `clip`.
+/// Points closer than `epsilon` to the previous one are merged, since they make triangulation fail
+pub(crate) fn clip_polygon(subject: &[Vec2], clip: &[Vec2], epsilon: f32) -> Vec<Vec2> {
+    let mut output: Vec<Vec2> = subject.to_vec();
+
+    for (i, &a) in clip.iter().enumerate() {
+        let b = clip[(i + 1) % clip.len()];
+        let edge = b - a;
+
+        let input = core::mem::take(&mut output);
+        let Some(&last) = input.last() else {
+            break;
+        };
+
+        // a point is inside if it is to the left of the edge
+        let inside = |point: Vec2| edge.perp_dot(point - a) >= 0.0;
+        let intersection = |p: Vec2, q: Vec2| {
+            let t = edge.perp_dot(p - a) / edge.perp_dot(p - q);
+            p.lerp(q, t)
+        };
+
+        let mut previous = last;
+        for &current in input.iter() {
+            match (inside(previous), inside(current)) {
+                (true, true) => output.push(current),
+                (true, false) => output.push(intersection(previous, current)),
+                (false, true) => {
+                    output.push(intersection(previous, current));
+                    output.push(current);
+                }
+                (false, false) => {}
+            }
+            previous = current;
+        }
+    }
+
+    output.dedup_by(|a, b| a.distance(*b) < epsilon);
+    if output.len() > 1 && output[0].distance(output[output.len() - 1]) < epsilon {
+        output.pop();
+    }
+
+    output
+}
+
+/// Signed area of a polygon, positive if it is counter-clockwise
+pub(crate) fn polygon_area(polygon: &[Vec2]) -> f32 {
+    let mut area = 0.0;
+    for (i, &a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        area += a.perp_dot(b);
+    }
+    area / 2.0
+}
+
+/// Reverses the polygon if it is clockwise
+pub(crate) fn counter_clockwise(mut polygon: Vec<Vec2>) -> Vec<Vec2> {
+    if polygon_area(&polygon) < 0.0 {
+        polygon.reverse();
+    }
+    polygon
+}
+
+/// Whether a counter-clockwise polygon is convex. Collinear points are allowed
+pub(crate) fn is_convex(polygon: &[Vec2]) -> bool {
+    let n = polygon.len();
+    (0..n).all(|i| {
+        let a = polygon[i];
+        let b = polygon[(i + 1) % n];
+        let c = polygon[(i + 2) % n];
+        (b - a).perp_dot(c - b) >= -CONVEX_EPSILON
+    })
+}
+
+/// Whether a point is inside of a convex polygon or on its edge, in either winding order
+pub(crate) fn convex_contains(polygon: &[Vec2], point: Vec2) -> bool {
+    let mut sides = polygon.iter().enumerate().map(|(i, &a)| {
+        let b = polygon[(i + 1) % polygon.len()];
+        (b - a).perp_dot(point - a)
+    });
+    sides.clone().all(|side| side >= 0.0) || sides.all(|side| side <= 0.0)
+}
+
+// tolerance for the cross products, so that points that are almost collinear don't make a polygon concave
+const CONVEX_EPSILON: f32 = 1e-6;
+
+/// Splits a simple counter-clockwise polygon with counter-clockwise holes into convex polygons.
+/// The holes are joined to the outline, then it is ear clipped into triangles,
+/// which are merged back together for as long as they stay convex (Hertel-Mehlhorn).
+/// This is not the smallest possible number of polygons, but it is never more than 4 times that
+pub(crate) fn convex_parts(outline: &[Vec2], holes: &[Vec<Vec2>]) -> Vec<Vec<Vec2>> {
+    if outline.len() < 3 {
+        return Vec::new();
+    }
+    if holes.is_empty() && is_convex(outline) {
+        return vec![outline.to_vec()];
+    }
+
+    let polygon = &bridge_holes(outline, holes)[..];
+
+    let mut parts: Vec<Vec<usize>> = ear_clip(polygon)
+        .into_iter()
+        .map(|triangle| triangle.to_vec())
+        .collect();
+
+    let mut merged = true;
+    while merged {
+        merged = false;
+        'search: for i in 0..parts.len() {
+            for j in (i + 1)..parts.len() {
+                if let Some(part) = merge_parts(&parts[i], &parts[j], polygon) {
+                    parts[i] = part;
+                    parts.swap_remove(j);
+                    merged = true;
+                    break 'search;
+                }
+            }
+        }
+    }
+
+    parts
+        .into_iter()
+        .map(|part| part.into_iter().map(|index| polygon[index]).collect())
+        .collect()
+}
+
+/// Joins the holes to the outline, turning them into a single polygon that goes around the outline and then into each hole.
+/// Each hole is joined with a bridge from its rightmost point to a point of the outline it can see. The bridge is walked twice,
+/// so the points at each end of it are duplicated.
+/// This is the method from "Triangulation by Ear Clipping" by David Eberly
+fn bridge_holes(outline: &[Vec2], holes: &[Vec<Vec2>]) -> Vec<Vec2> {
+    let mut polygon = outline.to_vec();
+
+    // the holes are walked clockwise, the opposite of the outline
+    let mut holes: Vec<Vec<Vec2>> = holes
+        .iter()
+        .filter(|hole| hole.len() >= 3)
+        .map(|hole| hole.iter().rev().copied().collect())
+        .collect();
+
+    // bridging the rightmost holes first guarantees that the bridges never cross
+    let rightmost = |hole: &Vec<Vec2>| hole.iter().map(|point| point.x).fold(f32::MIN, f32::max);
+    holes.sort_by(|a, b| rightmost(b).total_cmp(&rightmost(a)));
+
+    for hole in holes {
+        let (hole_start, &m) = hole
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.x.total_cmp(&b.x))
+            .expect("holes have at least 3 points");
+
+        let Some(bridge) = bridge_point(&polygon, m) else {
+            warn!("Could not join a hole to the outline of the glass, is it outside of the glass?");
+            continue;
+        };
+
+        // outline up to the bridge, around the hole and back to the bridge
+        let p = polygon[bridge];
+        let mut joined: Vec<Vec2> = polygon[..=bridge].to_vec();
+        joined.extend((0..=hole.len()).map(|i| hole[(hole_start + i) % hole.len()]));
+        joined.push(p);
+        joined.extend_from_slice(&polygon[bridge + 1..]);
+        polygon = joined;
+    }
+
+    polygon
+}
+
+/// Index of a point of the polygon that can be joined to `m` (a point inside of it) without crossing any edge
+fn bridge_point(polygon: &[Vec2], m: Vec2) -> Option<usize> {
+    let n = polygon.len();
+
+    // cast a ray from m to the right, and find the closest edge it hits
+    let mut closest: Option<(f32, usize)> = None;
+    for i in 0..n {
+        let a = polygon[i];
+        let b = polygon[(i + 1) % n];
+        if (a.y > m.y) == (b.y > m.y) {
+            continue;
+        }
+
+        let x = a.x + (m.y - a.y) / (b.y - a.y) * (b.x - a.x);
+        if x >= m.x && closest.is_none_or(|(closest_x, _)| x < closest_x) {
+            closest = Some((x, i));
+        }
+    }
+    let (x, edge) = closest?;
+    let hit = Vec2::new(x, m.y);
+
+    // the end of the edge furthest to the right is visible, unless another point of the polygon is in the way
+    let candidate = if polygon[edge].x > polygon[(edge + 1) % n].x {
+        edge
+    } else {
+        (edge + 1) % n
+    };
+    let p = polygon[candidate];
+    if p.y == m.y {
+        // the ray hit the point itself
+        return Some(candidate);
+    }
+
+    // any reflex point inside the triangle (m, hit, p) is in the way, the one with the smallest angle to the ray is visible instead
+    let triangle = if (hit - m).perp_dot(p - m) >= 0.0 {
+        [m, hit, p]
+    } else {
+        [m, p, hit]
+    };
+    let mut best: (f32, f32, usize) = (f32::MAX, f32::MAX, candidate);
+    for (i, &point) in polygon.iter().enumerate() {
+        if point == p || !in_triangle(point, triangle) {
+            continue;
+        }
+
+        let previous = polygon[(i + n - 1) % n];
+        let next = polygon[(i + 1) % n];
+        if (point - previous).perp_dot(next - point) > 0.0 {
+            // convex points can't be in the way
+            continue;
+        }
+
+        let offset = point - m;
+        let angle = offset.y.abs() / offset.length().max(f32::EPSILON);
+        let distance = offset.length_squared();
+        if (angle, distance) < (best.0, best.1) {
+            best = (angle, distance, i);
+        }
+    }
+
+    Some(best.2)
+}
+
+/// Triangulates a simple counter-clockwise polygon, returning the indices of each triangle
+fn ear_clip(polygon: &[Vec2]) -> Vec<[usize; 3]> {
+    let mut remaining: Vec<usize> = (0..polygon.len()).collect();
+    let mut triangles: Vec<[usize; 3]> = Vec::with_capacity(polygon.len());
+
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let corner = |i: usize| {
+            [
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            ]
+        };
+        let cross =
+            |[a, b, c]: [usize; 3]| (polygon[b] - polygon[a]).perp_dot(polygon[c] - polygon[b]);
+
+        // collinear points can never be ears, they are just dropped since they don't change the outline
+        if let Some(i) = (0..n).find(|&i| cross(corner(i)).abs() <= CONVEX_EPSILON) {
+            remaining.remove(i);
+            continue;
+        }
+
+        let ear = (0..n).find(|&i| {
+            let triangle = corner(i);
+            cross(triangle) > 0.0
+                && remaining.iter().all(|&other| {
+                    let points = triangle.map(|index| polygon[index]);
+                    // the points at the ends of the bridges to the holes are duplicated, and they are not in the way
+                    points.contains(&polygon[other]) || !in_triangle(polygon[other], points)
+                })
+        });
+
+        // only happens for self intersecting polygons, whatever is left is dropped
+        let Some(i) = ear else {
+            break;
+        };
+
+        triangles.push(corner(i));
+        remaining.remove(i);
+    }
+
+    if let [a, b, c] = remaining[..] {
+        triangles.push([a, b, c]);
+    }
+
+    triangles
+}
+
+/// Whether a point is inside (or on the border of) a counter-clockwise triangle
+fn in_triangle(point: Vec2, [a, b, c]: [Vec2; 3]) -> bool {
+    (b - a).perp_dot(point - a) >= 0.0
+        && (c - b).perp_dot(point - b) >= 0.0
+        && (a - c).perp_dot(point - c) >= 0.0
+}
+
+/// Joins two parts of a polygon that share an edge, if the result is still convex
+fn merge_parts(a: &[usize], b: &[usize], polygon: &[Vec2]) -> Option<Vec<usize>> {
+    for i in 0..a.len() {
+        let (u, v) = (a[i], a[(i + 1) % a.len()]);
+        // since both are counter-clockwise, the shared edge goes the other way in b
+        let Some(j) = (0..b.len()).find(|&j| b[j] == v && b[(j + 1) % b.len()] == u) else {
+            continue;
+        };
+
+        // all of a, starting at v and ending at u, then the rest of b
+        let mut merged: Vec<usize> = (1..=a.len()).map(|k| a[(i + k) % a.len()]).collect();
+        merged.extend((2..b.len()).map(|k| b[(j + k) % b.len()]));
+
+        let points: Vec<Vec2> = merged.iter().map(|&index| polygon[index]).collect();
+        return is_convex(&points).then_some(merged);
+    }
+
+    None
+}
+
+/// Center of mass of a polygon. Falls back to the average of the points for degenerate polygons
+pub(crate) fn polygon_centroid(polygon: &[Vec2]) -> Vec2 {
+    let area = polygon_area(polygon);
+    if area.abs() <= f32::EPSILON {
+        return polygon.iter().sum::<Vec2>() / polygon.len().max(1) as f32;
+    }
+
+    let mut centroid = Vec2::ZERO;
+    for (i, &a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        centroid += (a + b) * a.perp_dot(b);
+    }
+    centroid / (6.0 * area)
+}
+
+/// Center of mass of several polygons together, weighted by their area
+pub(crate) fn polygons_centroid(polygons: &[Vec<Vec2>]) -> Vec2 {
+    let mut total = 0.0;
+    let mut centroid = Vec2::ZERO;
+    for polygon in polygons {
+        let area = polygon_area(polygon).abs();
+        total += area;
+        centroid += polygon_centroid(polygon) * area;
+    }
+
+    if total <= f32::EPSILON {
+        let points = polygons.iter().flatten();
+        return points.clone().sum::<Vec2>() / points.count().max(1) as f32;
+    }
+    centroid / total
+}
+
+/// Axis aligned rectangle from (0, 0) to `size`, counter-clockwise
+pub(crate) fn rectangle(size: Vec2) -> [Vec2; 4] {
+    [
+        Vec2::ZERO,
+        Vec2::new(size.x, 0.0),
+        size,
+        Vec2::new(0.0, size.y),
+    ]
+}
+
+/// Outlines of the area covered by polygons that share edges, such as the triangles of a mesh or the pieces of a shard.
+/// Edges between two polygons cancel out, so the outer outline is counter-clockwise and the outlines of holes are clockwise
+pub(crate) fn boundary_loops(polygons: &[Vec<Vec2>]) -> Vec<Vec<Vec2>> {
+    const WELD_EPSILON: f32 = 1e-4;
+
+    // the same point can be in several polygons, so they are welded by position
+    let mut points: Vec<Vec2> = Vec::new();
+    let mut keys: HashMap<IVec2, usize> = HashMap::new();
+    let mut edges: HashMap<(usize, usize), i32> = HashMap::new();
+    for polygon in polygons {
+        let indices: Vec<usize> = polygon
+            .iter()
+            .map(|&point| {
+                *keys
+                    .entry((point / WELD_EPSILON).round().as_ivec2())
+                    .or_insert_with(|| {
+                        points.push(point);
+                        points.len() - 1
+                    })
+            })
+            .collect();
+
+        // interior edges are walked once in each direction and cancel out, only the boundary is left
+        for (i, &a) in indices.iter().enumerate() {
+            let b = indices[(i + 1) % indices.len()];
+            if a == b {
+                continue;
+            }
+            *edges.entry((a.min(b), a.max(b))).or_insert(0) += if a < b { 1 } else { -1 };
+        }
+    }
+
+    let mut next: HashMap<usize, usize> = edges
+        .into_iter()
+        .filter_map(|((a, b), count)| match count {
+            1 => Some((a, b)),
+            -1 => Some((b, a)),
+            _ => None,
+        })
+        .collect();
+
+    let mut loops: Vec<Vec<Vec2>> = Vec::new();
+    while let Some(&start) = next.keys().next() {
+        let mut current = start;
+        let mut polygon: Vec<Vec2> = Vec::new();
+        while let Some(following) = next.remove(&current) {
+            polygon.push(points[current]);
+            current = following;
+        }
+        if current == start && polygon.len() >= 3 {
+            loops.push(polygon);
+        }
+    }
+
+    loops
+}
+
+/// Outline of several convex polygons that make up a single shape, counter-clockwise.
+/// Falls back to the biggest polygon if they don't make a single closed outline
+pub(crate) fn polygons_outline(polygons: &[Vec<Vec2>]) -> Vec<Vec2> {
+    let largest = |polygons: &[Vec<Vec2>]| {
+        polygons
+            .iter()
+            .max_by(|a, b| polygon_area(a).abs().total_cmp(&polygon_area(b).abs()))
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    if polygons.len() <= 1 {
+        return largest(polygons);
+    }
+    let outline = largest(&boundary_loops(polygons));
+    if outline.is_empty() {
+        return largest(polygons);
+    }
+    counter_clockwise(outline)
+}
+
+/// Smallest interior angle of a counter-clockwise polygon, in radians
+pub(crate) fn min_angle(polygon: &[Vec2]) -> f32 {
+    let n = polygon.len();
+    (0..n)
+        .filter_map(|i| {
+            let point = polygon[i];
+            let to_previous = polygon[(i + n - 1) % n] - point;
+            let to_next = polygon[(i + 1) % n] - point;
+            if to_previous.length_squared() <= f32::EPSILON
+                || to_next.length_squared() <= f32::EPSILON
+            {
+                return None;
+            }
+
+            // reflex corners come out negative
+            let angle = ops::atan2(to_next.perp_dot(to_previous), to_previous.dot(to_next));
+            Some(if angle < 0.0 {
+                angle + core::f32::consts::TAU
+            } else {
+                angle
+            })
+        })
+        .fold(core::f32::consts::PI, f32::min)
+}
+
+/// Whether two shapes made of polygons share part of an edge, longer than `epsilon`
+pub(crate) fn polygons_touch(a: &[Vec<Vec2>], b: &[Vec<Vec2>], epsilon: f32) -> bool {
+    let edges = |polygons: &[Vec<Vec2>]| -> Vec<(Vec2, Vec2)> {
+        polygons
+            .iter()
+            .flat_map(|polygon| {
+                (0..polygon.len()).map(|i| (polygon[i], polygon[(i + 1) % polygon.len()]))
+            })
+            .collect()
+    };
+    let b_edges = edges(b);
+
+    edges(a).into_iter().any(|(p, q)| {
+        let Some(direction) = (q - p).try_normalize() else {
+            return false;
+        };
+        let length = p.distance(q);
+
+        b_edges.iter().any(|&(r, s)| {
+            // both ends of the other edge must be on this edge's line
+            if direction.perp_dot(r - p).abs() > epsilon
+                || direction.perp_dot(s - p).abs() > epsilon
+            {
+                return false;
+            }
+            let (start, end) = (direction.dot(r - p), direction.dot(s - p));
+            let overlap = start.max(end).min(length) - start.min(end).max(0.0);
+            overlap > epsilon
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(min: Vec2, size: f32) -> Vec<Vec2> {
+        vec![
+            min,
+            min + Vec2::new(size, 0.0),
+            min + Vec2::splat(size),
+            min + Vec2::new(0.0, size),
+        ]
+    }
+
+    /// Outlines (counter-clockwise) with their holes (also counter-clockwise)
+    fn shapes() -> Vec<(Vec<Vec2>, Vec<Vec<Vec2>>)> {
+        let l_shape = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(3.0, 0.0),
+            Vec2::new(3.0, 1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 3.0),
+            Vec2::new(0.0, 3.0),
+        ];
+        let star: Vec<Vec2> = (0..10)
+            .map(|i| {
+                let radius = if i % 2 == 0 { 2.0 } else { 0.8 };
+                let (sin, cos) = ops::sin_cos(i as f32 * core::f32::consts::TAU / 10.0);
+                Vec2::new(cos, sin) * radius
+            })
+            .collect();
+        let diamond_hole = vec![
+            Vec2::new(2.0, 1.0),
+            Vec2::new(2.5, 1.5),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(1.5, 1.5),
+        ];
+
+        vec![
+            (l_shape, Vec::new()),
+            (star, Vec::new()),
+            (square(Vec2::ZERO, 4.0), vec![square(Vec2::ONE, 1.0)]),
+            (
+                square(Vec2::ZERO, 4.0),
+                vec![
+                    square(Vec2::new(0.5, 0.5), 1.0),
+                    square(Vec2::new(2.5, 2.5), 1.0),
+                ],
+            ),
+            // the hole's rightmost point is level with a corner of the other hole
+            (
+                square(Vec2::ZERO, 4.0),
+                vec![diamond_hole, square(Vec2::new(2.8, 1.5), 0.5)],
+            ),
+        ]
+    }
+
+    fn expected_area(outline: &[Vec2], holes: &[Vec<Vec2>]) -> f32 {
+        polygon_area(outline) - holes.iter().map(|hole| polygon_area(hole)).sum::<f32>()
+    }
+
+    /// Whether two segments cross at a single point that is not an end of either of them
+    fn segments_cross(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> bool {
+        let side = |p: Vec2, q: Vec2, r: Vec2| (q - p).perp_dot(r - p);
+        let (d1, d2) = (side(a, b, c), side(a, b, d));
+        let (d3, d4) = (side(c, d, a), side(c, d, b));
+        d1 * d2 < 0.0 && d3 * d4 < 0.0
+    }
+
+    #[test]
+    fn ear_clip_keeps_the_area() {
+        for (outline, holes) in shapes() {
+            let polygon = bridge_holes(&outline, &holes);
+            let triangles = ear_clip(&polygon);
+
+            let mut area = 0.0;
+            for triangle in triangles {
+                let triangle_area = polygon_area(&triangle.map(|index| polygon[index]));
+                assert!(triangle_area > 0.0, "clockwise triangle in {outline:?}");
+                area += triangle_area;
+            }
+            assert!((area - expected_area(&outline, &holes)).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn bridged_polygons_stay_simple() {
+        for (outline, holes) in shapes() {
+            let polygon = bridge_holes(&outline, &holes);
+            assert_eq!(
+                polygon.len(),
+                outline.len() + holes.iter().map(|hole| hole.len() + 2).sum::<usize>()
+            );
+
+            let n = polygon.len();
+            for i in 0..n {
+                for j in (i + 1)..n {
+                    let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+                    let (c, d) = (polygon[j], polygon[(j + 1) % n]);
+                    assert!(
+                        !segments_cross(a, b, c, d),
+                        "edges {i} and {j} cross in {polygon:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn convex_parts_are_convex() {
+        for (outline, holes) in shapes() {
+            let parts = convex_parts(&outline, &holes);
+
+            let mut area = 0.0;
+            for part in parts {
+                assert!(part.len() >= 3);
+                assert!(is_convex(&part), "{part:?} is not convex");
+                area += polygon_area(&part);
+            }
+            assert!((area - expected_area(&outline, &holes)).abs() < 1e-4);
+        }
+    }
+}
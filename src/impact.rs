@@ -0,0 +1,115 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::*;
+
+/// Add this to a [`Glass`] to make it shatter by itself when something hits it hard enough, using avian's contacts.
+/// Requires [`ShatterPlugin`] to have been added, and the glass to have a collider.
+///
+/// When a contact with another body is strong enough, [`Shattered`] is inserted along with a [`ShatterImpact`] at the contact point.
+/// The impact keeps the radius and falloff of the [`ShatterImpact`] the glass already has, if any,
+/// and gets the direction of the hit and the kinetic energy the body lost to the glass, see [`ShatterImpact::energy`].
+///
+/// Both thresholds have to be reached, so set one to 0.0 to only use the other.
+/// Kinematic bodies don't produce any impulse, so use only `min_relative_speed` for them
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct ShatterOnImpact {
+    /// Minimum total contact impulse the glass needs to receive from a body in a single physics step
+    pub min_impulse: f32,
+    /// Minimum speed of the body relative to the glass, right before the impact
+    pub min_relative_speed: f32,
+}
+
+impl Default for ShatterOnImpact {
+    fn default() -> Self {
+        Self {
+            min_impulse: 5.0,
+            min_relative_speed: 3.0,
+        }
+    }
+}
+
+/// The glass, its settings, its transforms and the impact it already has, if any
+type ImpactGlass<'a> = (
+    Entity,
+    &'a Glass,
+    &'a ShatterOnImpact,
+    &'a GlobalTransform,
+    (&'a Position, &'a Rotation),
+    Option<&'a ShatterImpact>,
+);
+
+/// Shatters the glasses with [`ShatterOnImpact`] that were hit hard enough
+pub(super) fn shatter_on_impact(
+    glasses: Query<ImpactGlass, Without<Shattered>>,
+    bodies: Query<(&RigidBody, &LinearVelocity, &ComputedMass)>,
+    collisions: Collisions,
+    mut commands: Commands,
+) {
+    for (entity, glass, on_impact, glass_transf, (position, rotation), impact) in glasses.iter() {
+        let glass_velocity = bodies
+            .get(entity)
+            .map_or(Vec3::ZERO, |(_, velocity, _)| velocity.0);
+
+        for contacts in collisions.collisions_with(entity) {
+            // the contact point that took the largest impulse is where the glass was hit
+            let Some((normal, point)) = contacts
+                .manifolds
+                .iter()
+                .flat_map(|manifold| {
+                    manifold
+                        .points
+                        .iter()
+                        .map(move |point| (manifold.normal, point))
+                })
+                .max_by(|(_, a), (_, b)| a.normal_impulse.total_cmp(&b.normal_impulse))
+            else {
+                continue;
+            };
+
+            // the point on the glass, the other body, and the direction pointing into the glass
+            let (local_point, other, direction) = if contacts.collider1 == entity {
+                (
+                    point.local_point1,
+                    contacts.body2.unwrap_or(contacts.collider2),
+                    -normal,
+                )
+            } else {
+                (
+                    point.local_point2,
+                    contacts.body1.unwrap_or(contacts.collider1),
+                    normal,
+                )
+            };
+            let (velocity, mass, inverse_mass) = match bodies.get(other) {
+                Ok((body, velocity, mass)) if body.is_dynamic() => {
+                    (velocity.0, mass.value(), mass.inverse())
+                }
+                Ok((_, velocity, _)) => (velocity.0, 0.0, 0.0),
+                Err(_) => (Vec3::ZERO, 0.0, 0.0),
+            };
+
+            // the physics step already slowed the body down, so the speed it lost to the glass is added back
+            let impulse = contacts.total_normal_impulse_magnitude();
+            let speed_after = (velocity - glass_velocity).length();
+            let speed = speed_after + impulse * inverse_mass;
+            if impulse < on_impact.min_impulse || speed < on_impact.min_relative_speed {
+                continue;
+            }
+
+            let point = position.0 + rotation.0 * local_point;
+            let point = glass.project_to_glass(&glass_transf.compute_transform(), point);
+            commands.entity(entity).insert((
+                Shattered,
+                ShatterImpact {
+                    point,
+                    direction,
+                    // only the energy the body lost to the glass goes into the shards
+                    energy: 0.5 * mass * (speed * speed - speed_after * speed_after),
+                    ..impact.cloned().unwrap_or_else(|| ShatterImpact::new(point))
+                },
+            ));
+            break;
+        }
+    }
+}
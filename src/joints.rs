@@ -0,0 +1,86 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+/// Settings to connect neighboring shards with joints, see [`Glass::joints`](crate::Glass::joints).
+///
+/// When the glass is [`Shattered`](crate::Shattered), every pair of [`Shard::neighbors`](crate::Shard::neighbors) is connected with a [`FixedJoint`] (an entity with [`ShardJoint`]),
+/// so the shards behave as a single body until they are torn apart, and the cracks spread from wherever the forces are the largest.
+/// The shards need a [`RigidBody::Dynamic`] for the joints to do anything, for example from a hook on [`Shard`](crate::Shard).
+/// Making the shards that are [`Shard::on_frame`](crate::Shard::on_frame) static keeps the pane in its frame.
+///
+/// The joints are slightly soft, and a joint breaks (is despawned) once its shards are pulled apart by more than
+/// `break_force * compliance`, which is roughly how far the joint stretches under that force
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShardJoints {
+    /// Compliance (inverse of stiffness) of the joints, see [`FixedJoint::with_compliance`]. Must be larger than 0
+    pub compliance: f32,
+    /// Force it takes to break a joint
+    pub break_force: f32,
+}
+
+impl Default for ShardJoints {
+    fn default() -> Self {
+        Self {
+            compliance: 0.0001,
+            break_force: 500.0,
+        }
+    }
+}
+
+/// Marks a [`FixedJoint`] between two shards, created by [`ShardJoints`]
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ShardJoint {
+    /// Distance the shards can be pulled apart before the joint breaks
+    pub break_distance: f32,
+}
+
+/// Spawns a joint between every pair of neighbors. `shards` are the entity and centroid of each shard,
+/// and `neighbors` the indices of the neighbors of each one (see [`Shard::neighbors`](crate::Shard::neighbors)).
+/// `scale` is the world scale of the shards, since avian's anchors are not scaled with the body
+pub(crate) fn spawn_shard_joints(
+    commands: &mut Commands,
+    joints: &ShardJoints,
+    shards: &[(Entity, Vec3)],
+    neighbors: &[Vec<usize>],
+    scale: Vec3,
+) {
+    for (i, (entity, centroid)) in shards.iter().enumerate() {
+        for &j in neighbors[i].iter().filter(|&&j| j > i) {
+            let (other, other_centroid) = shards[j];
+
+            // anchored halfway between both shards, relative to the center of each one
+            let anchor = (*centroid + other_centroid) / 2.0;
+            commands.spawn((
+                FixedJoint::new(*entity, other)
+                    .with_local_anchor_1((anchor - *centroid) * scale)
+                    .with_local_anchor_2((anchor - other_centroid) * scale)
+                    .with_compliance(joints.compliance),
+                ShardJoint {
+                    break_distance: joints.break_force * joints.compliance,
+                },
+            ));
+        }
+    }
+}
+
+/// Breaks the joints between shards that were pulled too far apart, or whose shards were despawned
+pub(super) fn break_shard_joints(
+    joints: Query<(Entity, &FixedJoint, &ShardJoint)>,
+    bodies: Query<(&Position, &Rotation)>,
+    mut commands: Commands,
+) {
+    for (entity, joint, shard_joint) in joints.iter() {
+        let (Ok((position1, rotation1)), Ok((position2, rotation2))) =
+            (bodies.get(joint.entity1), bodies.get(joint.entity2))
+        else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        let anchor1 = position1.0 + rotation1.0 * joint.local_anchor1;
+        let anchor2 = position2.0 + rotation2.0 * joint.local_anchor2;
+        if anchor1.distance(anchor2) > shard_joint.break_distance {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use crate::*;
+
+/// Presets for the different types of glass, used with [`Glass::from_kind`].
+/// Each one picks the cell density, [`FractureMode`], [`SeedGenerator`], [`ShardCollider`] and [`ShardPhysics`].
+/// All of them are just fields of [`Glass`], so they can be changed afterwards
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GlassKind {
+    /// Regular window glass. Breaks into large and sharp shards around the impact, in a [`Spiderweb`] pattern
+    #[default]
+    Annealed,
+    /// Safety glass, such as car side windows and shower doors.
+    /// Bursts into many small cube-ish pieces over the whole glass, which get cheap box colliders
+    Tempered,
+    /// Safety glass with a plastic layer in the middle, such as car windshields.
+    /// Cracks into a dense web of small pieces that stay attached to the glass, see [`Laminated`]
+    Laminated,
+}
+
+impl GlassKind {
+    /// Number of cells per unit of distance, see [`Glass::new_with_density`]
+    pub fn cells_per_unit(&self) -> f32 {
+        match self {
+            GlassKind::Annealed => 2.0,
+            GlassKind::Tempered => 8.0,
+            GlassKind::Laminated => 4.0,
+        }
+    }
+
+    /// How the glass is split into shards
+    pub fn fracture_mode(&self) -> FractureMode {
+        match self {
+            GlassKind::Annealed => FractureMode::Spiderweb(Spiderweb::default()),
+            GlassKind::Tempered => FractureMode::Voronoi,
+            GlassKind::Laminated => FractureMode::Spiderweb(Spiderweb {
+                spokes: 20,
+                first_ring: 0.05,
+                ring_growth: 1.3,
+                ..default()
+            }),
+        }
+    }
+
+    /// Where the cell points are placed. Only used by [`GlassKind::Tempered`], the others use a [`Spiderweb`]
+    pub fn seeds(&self) -> Arc<dyn SeedGenerator> {
+        match self {
+            // evenly spaced points make the pieces roughly cubic
+            GlassKind::Tempered => Arc::new(PoissonDisk::default()),
+            GlassKind::Annealed | GlassKind::Laminated => Arc::new(Radial),
+        }
+    }
+
+    /// Which collider each shard gets
+    pub fn shard_collider(&self) -> ShardCollider {
+        match self {
+            GlassKind::Annealed | GlassKind::Laminated => ShardCollider::ConvexHull,
+            GlassKind::Tempered => ShardCollider::Cuboid,
+        }
+    }
+
+    /// Suggested physics material for the shards
+    pub fn shard_physics(&self) -> ShardPhysics {
+        match self {
+            GlassKind::Annealed | GlassKind::Tempered => ShardPhysics::default(),
+            // the plastic layer makes it less slippery and bouncy
+            GlassKind::Laminated => ShardPhysics {
+                friction: 0.7,
+                restitution: 0.05,
+                ..default()
+            },
+        }
+    }
+
+    /// Whether the shards stay attached to the glass, see [`Laminated`]
+    pub fn laminated(&self) -> Option<Laminated> {
+        match self {
+            GlassKind::Laminated => Some(Laminated::default()),
+            GlassKind::Annealed | GlassKind::Tempered => None,
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::*;
+
+/// Settings for laminated glass, see [`Glass::laminated`].
+///
+/// When laminated glass is [`Shattered`], the shards are created as children of the glass, with the [`AttachedShard`] component.
+/// Their colliders become part of the glass' rigid body (if it has one), so the glass cracks but keeps behaving as a single body.
+/// The mesh and collider of the glass itself are removed, since the shards replace them.
+///
+/// Attached shards that are hit hard enough come loose: they are detached from the glass and given a [`RigidBody::Dynamic`].
+///
+/// **Note:** since the shards are children of the glass, hiding the glass also hides them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Laminated {
+    /// Minimum total contact impulse an attached shard needs to receive in a single physics step to come loose
+    pub release_impulse: f32,
+}
+
+impl Default for Laminated {
+    fn default() -> Self {
+        Self {
+            release_impulse: 5.0,
+        }
+    }
+}
+
+/// Marks a shard of [`Laminated`] glass that is still attached to its glass
+#[derive(Component, Debug)]
+pub struct AttachedShard;
+
+/// Detaches the shards of laminated glass that received a large enough impulse
+pub(super) fn release_attached_shards(
+    shards: Query<(Entity, &ShardOf, &GlobalTransform), With<AttachedShard>>,
+    glasses: Query<&Glass>,
+    collisions: Collisions,
+    mut commands: Commands,
+) {
+    for (shard, shard_of, shard_transf) in shards.iter() {
+        let Some(laminated) = glasses
+            .get(shard_of.0)
+            .ok()
+            .and_then(|glass| glass.laminated)
+        else {
+            continue;
+        };
+
+        let impulse: f32 = collisions
+            .collisions_with(shard)
+            .map(|contacts| contacts.total_normal_impulse_magnitude())
+            .sum();
+
+        if impulse >= laminated.release_impulse {
+            commands
+                .entity(shard)
+                .remove::<(ChildOf, AttachedShard)>()
+                .insert((shard_transf.compute_transform(), RigidBody::Dynamic));
+        }
+    }
+}
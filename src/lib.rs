@@ -8,15 +8,19 @@
 //!
 //! **Plugin**
 //!
-//! Add the [`ShatterPlugin`] to register the needed hooks and resources.
+//! Add the [`ShatterPlugin`] to register the needed observers, systems and resources.
 //!
 //! **Creating glass**
 //!
-//! Add the [`Glass`] component to an entity, using the [`Transform::scale`] as width, height and thickness. A helper is available in [`AutoGlass`] to add other needed components automatically, such as a mesh and a transform with the correct scale.
+//! Add the [`Glass`] component to an entity, with its width, height and thickness in [`Glass::size`]. The [`Transform`] only places it in the world. A helper is available in [`AutoGlass`] to add other needed components automatically, such as a mesh and a collider of the correct size.
+//! To turn an existing flat mesh (for example a window from a glTF scene) into glass, add [`MeshGlass`] to it instead.
 //!
 //! **Shattering glass**
 //!
 //! Add the [`Shattered`] component to an entity that has [`Glass`], and glass shards will automatically be created.
+//! Insert a [`ShatterImpact`] along with it to make the glass break around the point where it was hit.
+//! Insert [`PartialShatter`] instead of [`Shattered`] to only break the glass around the impact and keep the rest of it, like a bullet hole.
+//! Or add [`ShatterOnImpact`] to a glass with a collider to have it shattered automatically when something hits it hard enough, at the point of contact.
 //!
 //! # Customizing behaviour
 //!
@@ -30,6 +34,40 @@
 //!
 //! Are entities with the [`Shard`] component.
 //! You can use this to, for example, make an OnAdd hook that automatically makes shards have a dynamic rigid body when added.
+//! Each shard's [`Transform`] is placed at its center of mass, see [`Shard::centroid`]. [`Shard`] also has its outline, area, volume, size, sharpness and neighbors, for gameplay like damage from sharp shards or picking sounds.
+//! Shards can be [`Shattered`] too, breaking them into smaller shards, for example when a big shard hits the floor.
+//! Glass can be the child of another entity, such as a car door. Its shards are then placed in the world, or under the same parent, see [`ShardSpace`].
+//! If the glass (or the rigid body it is mounted on) is moving, each shard gets the [`LinearVelocity`] and [`AngularVelocity`] of the point it came from, so the debris keeps moving with it.
+//! Give the [`ShatterImpact`] a direction and energy ([`ShatterImpact::with_energy`]) to also push the shards out the other side of the glass, spinning:
+//! a bullet sprays the shards near the impact, a body slam pushes the whole pane.
+//!
+//! **Glass shapes**
+//!
+//! Glass is a rectangle by default. Use [`Glass::with_shape`] for round, arched or any other polygonal glass, see [`GlassShape`]. The shards are clipped to the outline.
+//! Holes can be cut into the glass with [`Glass::with_hole`], and it can be bent into a cylinder or a sphere with [`Glass::with_curvature`].
+//!
+//! **Structural support**
+//!
+//! Add [`StructuralSupport`] to a glass to make its shards hold each other up. Whenever shards fall or are removed,
+//! the ones that are no longer connected to the frame of the glass fall too (see [`Unsupported`]).
+//!
+//! To make the shards hold together physically instead, use [`Glass::with_joints`] to connect neighboring shards with joints that break under enough force, see [`ShardJoints`].
+//!
+//! **Falling shards**
+//!
+//! Add [`ShardRelease`] to a glass to make its shards fall in waves that spread from the impact, instead of all at once.
+//!
+//! **Solid glass**
+//!
+//! For glass blocks, ice cubes and other solid objects, add [`SolidGlass`] to an entity with a convex [`Mesh3d`] instead. [`Shattered`] splits its whole volume into convex shards.
+//!
+//! **Progressive damage**
+//!
+//! Add [`GlassHealth`] to a glass and trigger [`GlassHit`]s on it. Weak hits only crack it (see [`Cracked`]), and it shatters along those cracks once it takes enough damage.
+//!
+//! **Deterministic shattering**
+//!
+//! By default, every shatter is random. Add a [`ShatterRng`] with a fixed seed to a glass to always get the same shards for the same inputs.
 //!
 //! **Shard relationship**
 //!
@@ -51,66 +89,303 @@
 //! Currently, the glass is broken into cells using a voronoi diagram. These cells are then extruded to 3D, creating a shard.
 //!
 //! Each cell's position is chosen by dividing the glass into a grid, to ensure they are all roughly the same size, and then picking random points within each cell of the grid.
+//! If the glass has a [`ShatterImpact`], the same number of cells is instead packed around the impact point, becoming sparser the further away they are from it.
+//!
+//! This is just the default pattern, each glass can pick a different [`SeedGenerator`] with [`Glass::with_seeds`], such as [`PoissonDisk`] or a fixed [`PointList`].
+//!
+//! Other than voronoi cells, the glass can also be split into triangles, into wedges around the impact or into a [`Spiderweb`] of cracks, see [`FractureMode`].
+//!
+//! For the common types of glass, [`Glass::from_kind`] picks all of these settings from a [`GlassKind`] preset.
 //!
 //! # Contributing
 //!
 //! This plugin is in very early development. PRs and forks are welcome. See TODO.md for a list of things that are missing
 
+use std::sync::Arc;
+
 use avian3d::prelude::*;
-use bevy::{
-    asset::RenderAssetUsages,
-    platform::collections::HashMap,
-    prelude::*,
-    render::mesh::{Indices, PrimitiveTopology},
+use bevy::prelude::*;
+use geometry::{
+    convex_parts, counter_clockwise, min_angle, polygon_area, polygons_centroid, polygons_outline,
+    polygons_touch,
 };
-use voronator::{delaunator::*, VoronoiDiagram};
 
 mod autoglass;
 pub use autoglass::*;
 
+mod cracks;
+pub use cracks::*;
+
+mod curved;
+pub use curved::*;
+
+mod fracture;
+pub use fracture::*;
+
+mod geometry;
+
+mod impact;
+pub use impact::*;
+
+mod joints;
+pub use joints::*;
+
+mod kind;
+pub use kind::*;
+
+mod laminated;
+pub use laminated::*;
+
+mod mesh_glass;
+pub use mesh_glass::*;
+
+mod partial;
+pub use partial::*;
+
+mod physics;
+pub use physics::*;
+
 mod plugin;
 pub use plugin::*;
 
+mod release;
+pub use release::*;
+
+mod rng;
+pub use rng::*;
+
+mod seeds;
+pub use seeds::*;
+
+mod shape;
+pub use shape::*;
+
+mod solid;
+pub use solid::*;
+
+mod spiderweb;
+pub use spiderweb::*;
+
+mod support;
+pub use support::*;
+
 // TODO: store num_cell_points as floats??
-/// The component that marks an entity as glass that can be shattered. No other components are added to the entity, so you should add a material, mesh, etc. Feel free to take the mesh from [`GlassMesh`]. See [`AutoGlass`] for a quick way to spawn glass with some default components.
+/// The component that marks an entity as glass that can be shattered. No other components are added to the entity, so you should add a material, mesh, etc. Feel free to use [`Glass::mesh`] and [`Glass::collider`]. See [`AutoGlass`] for a quick way to spawn glass with some default components.
 ///
-/// **Note:** the [`Transform::scale`] will be used to get the width (x), height (y) and thickness (z) of the glass.
+/// **Note:** the width (x), height (y) and thickness (z) of the glass are in [`Glass::size`], and the glass is centered on its [`Transform`].
+/// The transform is only a placement, scaling it (or one of its parents) scales the glass and its shards like any other mesh.
 #[derive(Component, Clone, Debug)]
 pub struct Glass {
     /// The number of cell points to be used along the width and the height. Either passed in manually or through [`Glass::new_with_density`].
     /// Increasing this number means that more shattered glass pieces will be spawned, with smaller sizes,
     /// increasing computational cost
     pub num_cell_points: UVec2,
+    /// Width (x), height (y) and thickness (z) of the glass. Given to every constructor, and replaced by [`AutoGlass`] and [`MeshGlass`]
+    pub size: Vec3,
+    /// Decides where the cell points are placed when the glass is shattered. Defaults to [`Radial`].
+    /// See [`SeedGenerator`] for the available patterns
+    pub seeds: Arc<dyn SeedGenerator>,
+    /// How the glass is split into shards. Defaults to [`FractureMode::Voronoi`]
+    pub fracture: FractureMode,
+    /// Which collider each shard gets. Defaults to [`ShardCollider::ConvexHull`]
+    pub collider: ShardCollider,
+    /// Physics material given to each shard. Defaults to `None`, using avian's defaults
+    pub physics: Option<ShardPhysics>,
+    /// If set, the glass cracks but the shards stay attached to it until hit hard enough. Defaults to `None`.
+    /// See [`Laminated`]
+    pub laminated: Option<Laminated>,
+    /// Outline of the glass. Defaults to [`GlassShape::Rectangle`]
+    pub shape: GlassShape,
+    /// Holes in the glass. Defaults to none
+    pub holes: Vec<GlassHole>,
+    /// Bends the glass into a curve. Defaults to `None`, a flat glass
+    pub curvature: Option<GlassCurvature>,
+    /// Where the shards are placed if the glass has a parent. Defaults to [`ShardSpace::World`]
+    pub shard_space: ShardSpace,
+    /// If set, neighboring shards are connected with joints that break under enough force. Defaults to `None`.
+    /// See [`ShardJoints`]
+    pub joints: Option<ShardJoints>,
+}
+
+/// Where the shards of a [`Glass`] that has a parent (such as a car door or a moving platform) are spawned.
+/// Glass without a parent always spawns its shards at the root, right where it is
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShardSpace {
+    /// The shards are root entities, placed in the world where the glass is (using its [`GlobalTransform`]).
+    /// They don't follow the parent after that, which is what loose shards flying off should usually do
+    #[default]
+    World,
+    /// The shards are children of the same parent as the glass, so they keep moving with it
+    Parent,
 }
 
 impl Glass {
+    /// Thickness of the glass if it is not given, see [`Glass::new_with_density`]
+    pub const DEFAULT_THICKNESS: f32 = 0.1;
+
     /// Generates glass using a density value (number of cells per unit of distance),
-    /// automatically computing the number of cells
+    /// automatically computing the number of cells. The thickness is [`Glass::DEFAULT_THICKNESS`]
     pub fn new_with_density(width: f32, height: f32, cells_per_unit: f32) -> Self {
         let cells_x: u32 = (cells_per_unit * width).floor() as u32;
         let cells_y: u32 = (cells_per_unit * height).floor() as u32;
 
+        Self::new(
+            UVec2::new(cells_x, cells_y),
+            Vec3::new(width, height, Self::DEFAULT_THICKNESS),
+        )
+    }
+
+    /// Generates glass using an XY grid for the number of cells, with the given width (x), height (y) and thickness (z)
+    pub fn new(num_cell_points: UVec2, size: Vec3) -> Self {
+        Self {
+            num_cell_points,
+            size,
+            seeds: Arc::new(Radial),
+            fracture: FractureMode::default(),
+            collider: ShardCollider::default(),
+            physics: None,
+            laminated: None,
+            shape: GlassShape::default(),
+            holes: Vec::new(),
+            curvature: None,
+            shard_space: ShardSpace::default(),
+            joints: None,
+        }
+    }
+
+    /// Generates glass of a certain [`GlassKind`], using the preset for its density, fracture pattern, colliders and physics material.
+    /// Any of these can be changed afterwards, for example `Glass::from_kind(GlassKind::Tempered, 2.0, 1.0).with_collider(ShardCollider::ConvexHull)`
+    pub fn from_kind(kind: GlassKind, width: f32, height: f32) -> Self {
         Self {
-            num_cell_points: UVec2::new(cells_x, cells_y),
+            seeds: kind.seeds(),
+            fracture: kind.fracture_mode(),
+            collider: kind.shard_collider(),
+            physics: Some(kind.shard_physics()),
+            laminated: kind.laminated(),
+            ..Self::new_with_density(width, height, kind.cells_per_unit())
         }
     }
 
-    /// Generates glass using an XY grid for the number of cells
-    pub fn new(num_cell_points: UVec2) -> Self {
-        Self { num_cell_points }
+    /// Changes the width (x), height (y) and thickness (z) of the glass. The number of cell points stays the same
+    pub fn with_size(mut self, size: Vec3) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Changes the thickness of the glass
+    pub fn with_thickness(mut self, thickness: f32) -> Self {
+        self.size.z = thickness;
+        self
+    }
+
+    /// Changes the pattern used to place the cell points, for example `Glass::new(UVec2::new(10, 10), Vec3::new(1.0, 1.0, 0.1)).with_seeds(PoissonDisk::default())`
+    pub fn with_seeds(mut self, seeds: impl SeedGenerator) -> Self {
+        self.seeds = Arc::new(seeds);
+        self
+    }
+
+    /// Changes how the glass is split into shards, for example `Glass::new(UVec2::new(10, 10), Vec3::new(1.0, 1.0, 0.1)).with_fracture(FractureMode::Triangles)`
+    pub fn with_fracture(mut self, fracture: FractureMode) -> Self {
+        self.fracture = fracture;
+        self
+    }
+
+    /// Changes which collider each shard gets
+    pub fn with_collider(mut self, collider: ShardCollider) -> Self {
+        self.collider = collider;
+        self
+    }
+
+    /// Changes the physics material given to each shard
+    pub fn with_physics(mut self, physics: ShardPhysics) -> Self {
+        self.physics = Some(physics);
+        self
+    }
+
+    /// Makes the glass behave like laminated glass, see [`Laminated`]
+    pub fn with_laminated(mut self, laminated: Laminated) -> Self {
+        self.laminated = Some(laminated);
+        self
+    }
+
+    /// Changes the outline of the glass, for example `Glass::new(UVec2::new(10, 10), Vec3::new(1.0, 1.0, 0.1)).with_shape(GlassShape::Circle(Circle::new(1.0)))`
+    pub fn with_shape(mut self, shape: GlassShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// Adds a hole to the glass, for example `Glass::new(UVec2::new(10, 10), Vec3::new(1.0, 1.0, 0.1)).with_hole(GlassHole::new(GlassShape::Rectangle, Rect::new(0.4, 0.1, 0.6, 0.2)))`
+    pub fn with_hole(mut self, hole: GlassHole) -> Self {
+        self.holes.push(hole);
+        self
+    }
+
+    /// Bends the glass into a curve, for example `Glass::new(UVec2::new(10, 10), Vec3::new(1.0, 1.0, 0.1)).with_curvature(GlassCurvature::Cylindrical { radius: 5.0 })`
+    pub fn with_curvature(mut self, curvature: GlassCurvature) -> Self {
+        self.curvature = Some(curvature);
+        self
+    }
+
+    /// Changes where the shards are placed if the glass has a parent, see [`ShardSpace`]
+    pub fn with_shard_space(mut self, shard_space: ShardSpace) -> Self {
+        self.shard_space = shard_space;
+        self
+    }
+
+    /// Connects neighboring shards with joints, see [`ShardJoints`]
+    pub fn with_joints(mut self, joints: ShardJoints) -> Self {
+        self.joints = Some(joints);
+        self
+    }
+
+    /// Whether the glass is a plain rectangle, with no [`GlassShape`] or [`GlassHole`]s
+    fn is_rectangle(&self) -> bool {
+        self.shape == GlassShape::Rectangle && self.holes.is_empty()
+    }
+
+    /// The outline of the glass (with its holes) split into convex polygons,
+    /// relative to the bottom left of a glass of the given width and height
+    fn convex_parts(&self, size: Vec2) -> Vec<Vec<Vec2>> {
+        let holes: Vec<Vec<Vec2>> = self.holes.iter().map(|hole| hole.outline(size)).collect();
+        convex_parts(&self.shape.outline(size), &holes)
+    }
+
+    /// Mesh of this glass, following its [`Glass::size`], [`GlassShape`], [`GlassHole`]s and [`GlassCurvature`].
+    /// It is centered on the glass entity. `None` if the outline is degenerate
+    pub fn mesh(&self) -> Option<Mesh> {
+        outline_mesh(
+            &self.convex_parts(self.size.xy()),
+            self.size,
+            self.curvature.as_ref(),
+        )
+    }
+
+    /// Collider of this glass, following its [`Glass::size`], [`GlassShape`], [`GlassHole`]s and [`GlassCurvature`], made of convex hulls.
+    /// A flat rectangle gets a cuboid instead. Like the mesh, it is centered on the glass entity. `None` if the outline is degenerate
+    pub fn collider(&self) -> Option<Collider> {
+        if self.is_rectangle() && self.curvature.is_none() {
+            return Some(Collider::cuboid(self.size.x, self.size.y, self.size.z));
+        }
+
+        outline_collider(
+            &self.convex_parts(self.size.xy()),
+            self.size,
+            self.curvature.as_ref(),
+        )
     }
 
-    // TODO: how to generate a lot of random numbers as fast as possible?
-    // TODO: break this into more than one method
-    /// Spawns the entities that make up the shattered glass and makes the old glass invisible.
-    fn shatter(
+    /// Splits the glass into flat cells, before they are extruded into shards.
+    /// `size` is the width, height and thickness of the glass.
+    /// The cells are clipped to the [`GlassShape`], or to what is left of the glass if part of it was already broken off (see [`IntactGlass`]).
+    /// The vertices are relative to the bottom left of the glass, see [`shatter_hook`] for how they are placed in the world.
+    fn cells(
         &self,
-        glass_entity: Entity,
-        glass_transf: &Transform,
-        glass_material: Handle<StandardMaterial>,
-        mut commands: Commands,
-        mut meshes: ResMut<Assets<Mesh>>,
-    ) {
+        size: Vec3,
+        impact: Option<&ShatterImpact>,
+        cracked: Option<&Cracked>,
+        intact: Option<&IntactGlass>,
+        rng: &mut ShatterRng,
+    ) -> Vec<Cell> {
         // voronator crashes when the cells overlap or are too close
         // when using a lot of cells or a very small glass, this actually becomes a pain
         // you might also just get unlucky with the RNG gods and have the game crash for no apparent reason
@@ -118,203 +393,354 @@ impl Glass {
         // FIX: also consider the case where it is not possible to conserve this distance, but at that point it's mostly user error
         const EPSILON: f32 = 0.001;
 
-        let width = glass_transf.scale.x;
-        let height = glass_transf.scale.y;
-        let thickness = glass_transf.scale.z;
+        // the generator can be user provided, so make sure the points are usable
+        let mut seeds = SeedSet::new(size.xy(), EPSILON);
+        if self.fracture.uses_seeds() {
+            // the cracks go first, so that the shards follow them as closely as possible
+            if let Some(cracked) = cracked {
+                for &seed in cracked.seeds.iter() {
+                    seeds.insert(seed);
+                }
+            }
 
-        // the full cell width, used to determine the center of each cell
-        let cell_width: f32 = width / self.num_cell_points.x as f32;
-        let cell_height: f32 = height / self.num_cell_points.y as f32;
+            let context = SeedContext {
+                size: size.xy(),
+                num_cell_points: self.num_cell_points,
+                impact,
+                min_distance: EPSILON,
+            };
 
-        // the max offset a point can be in from the center of the cell
-        let cell_offset = Vec2::new((cell_width / 2.0) - EPSILON, (cell_height / 2.0) - EPSILON);
+            for seed in self.seeds.generate(&context, rng) {
+                seeds.insert(seed);
+            }
+        }
 
-        // 2*cell_offset, representing the max offset from one edge of the cell to the other, instead of just to the center
-        let full_cell_offset = cell_offset * 2.0;
+        let mut cells = self
+            .fracture
+            .cells(seeds.points(), size.xy(), impact, rng, EPSILON);
 
-        let mut cells: Vec<(f64, f64)> = Vec::new();
+        // the modes that ignore the seeds are cut along the cracks instead
+        if let (Some(cracked), false) = (cracked, self.fracture.uses_seeds()) {
+            cells = cracked.split(cells, EPSILON);
+        }
 
-        // build the cells from bottom left to top right
-        for y in 0..self.num_cell_points.y {
-            for x in 0..self.num_cell_points.x {
-                let cell_center = Vec2::new(x as f32 * cell_width, y as f32 * cell_height);
+        match intact {
+            Some(intact) => intact.clip(cells, EPSILON),
+            // the cells already cover exactly the whole glass
+            None if self.is_rectangle() => cells,
+            None => IntactGlass {
+                polygons: self.convex_parts(size.xy()),
+            }
+            .clip(cells, EPSILON),
+        }
+    }
 
-                let bottom_left = cell_center - cell_offset;
+    /// Extrudes a cell into a shard, returning its mesh and collider. `None` if the cell could not be triangulated.
+    /// `size` is the width, height and thickness of the glass
+    fn build_shard(&self, cell: Cell, size: Vec3) -> Option<FracturedShard> {
+        let Some(mesh) = extrude_glass(&cell.polygons, size, self.curvature.as_ref()) else {
+            // delaunay failed
+            warn!("Failed to triangulate a glass shard, skipping it");
+            return None;
+        };
 
-                // generate a random float inside the cell, using cell_offset_2
-                // then offset it by the bottom left position
-                let rand = Vec2::new(fastrand::f32(), fastrand::f32());
+        // the middle of the shard's thickness, bent along with the mesh
+        let centroid = polygons_centroid(&cell.polygons).extend(-size.z / 2.0);
+        let centroid = match &self.curvature {
+            Some(curvature) => curvature.bend(centroid, size.xy()),
+            None => centroid,
+        };
 
-                let position = (rand * full_cell_offset) + bottom_left;
+        // each shard is placed at its own center, so that it rotates around it
+        let mesh = mesh.translated_by(-centroid);
+        let collider = self.collider.build(&mesh);
 
-                cells.push((position.x as f64, position.y as f64));
-            }
-        }
+        let polygons: Vec<Vec<Vec2>> = cell.polygons.into_iter().map(counter_clockwise).collect();
+        let outline = polygons_outline(&polygons);
+        let area: f32 = polygons.iter().map(|polygon| polygon_area(polygon)).sum();
+        let radius = mesh
+            .attribute(Mesh::ATTRIBUTE_POSITION)
+            .and_then(|positions| positions.as_float3())
+            .map_or(0.0, |positions| {
+                positions
+                    .iter()
+                    .map(|&position| Vec3::from(position).length())
+                    .fold(0.0, f32::max)
+            });
 
-        let voronoi_diagram =
-            VoronoiDiagram::<Point>::from_tuple(&(0., 0.), &(width as f64, height as f64), &cells)
-                .expect("Error generating Voronoi diagram");
+        Some(FracturedShard {
+            pos: cell.pos,
+            centroid,
+            min_angle: min_angle(&outline),
+            outline,
+            area,
+            volume: area * size.z,
+            radius,
+            neighbors: Vec::new(),
+            on_frame: false,
+            polygons,
+            generation: 0,
+            mesh,
+            collider,
+        })
+    }
 
-        // to allow shard baking, this is now done manually by the user
-        // // mark original entity as invisible
-        // commands.entity(glass_entity).insert(Visibility::Hidden);
+    /// Where the shards of an entity (this glass or one of its shards) with the given transforms and parent go, following [`Glass::shard_space`]
+    fn placement(
+        &self,
+        transform: &Transform,
+        global_transform: &GlobalTransform,
+        parent: Option<&ChildOf>,
+    ) -> ShardPlacement {
+        match parent {
+            // the transform is already in world space, and unlike the global transform it is never out of date
+            None => ShardPlacement {
+                transform: *transform,
+                parent: None,
+                to_world: GlobalTransform::from(*transform),
+                velocity: None,
+                push: None,
+            },
+            Some(parent) if self.shard_space == ShardSpace::Parent => ShardPlacement {
+                transform: *transform,
+                parent: Some(parent.parent()),
+                to_world: *global_transform,
+                velocity: None,
+                push: None,
+            },
+            Some(_) => ShardPlacement {
+                transform: global_transform.compute_transform(),
+                parent: None,
+                to_world: *global_transform,
+                velocity: None,
+                push: None,
+            },
+        }
+    }
 
+    /// Where the shards of `entity`, which is this glass, go
+    fn shard_placement(
+        &self,
+        entity: Entity,
+        transform: &Transform,
+        global_transform: &GlobalTransform,
+        parent: Option<&ChildOf>,
+    ) -> ShardPlacement {
         // it is (much) easier to offset the vertices themselves than the transform,
         // so every shard uses this transform which corresponds to the bottom left of the glass
-        let shard_transform = glass_transf.with_scale(Vec3::ONE)
-            * Transform::from_translation(Vec3::new(-width, -height, thickness) / 2.0);
-
-        // iterate voronoi cells and triangulate them
-        // also need to extrude since we want 3D mesh
-        // TODO: consider generating the normals myself
-        // TODO: extruding vertices was way harder than I expected, I have no idea thy I use negative values like -width and -thickness,
-        // if it works it works. try to replace this with some lib that can extrude meshes in the future, I couldn't find anything decent and lightweight
-        for (cell_id, cell) in voronoi_diagram.cells().iter().enumerate() {
-            let points = cell.points();
-            let shard_center = cells[cell_id];
-
-            // if the cell has less than 3 points it can't be triangulated, this is extremely rare, have to find out why it happens
-            if let Some(delaunay) = triangulate::<Point>(points) {
-                // Original vertices are used as the top (z = 0)
-                let mut verts: Vec<Vec3> = points
-                    .iter()
-                    .map(|point| Vec3::new(point.x as f32, point.y as f32, 0.0))
-                    .collect();
-                let n = verts.len();
+        if self.laminated.is_some() {
+            // attached to the glass itself
+            return ShardPlacement {
+                transform: bottom_left(self.size),
+                parent: Some(entity),
+                to_world: *global_transform * bottom_left(self.size),
+                velocity: None,
+                push: None,
+            };
+        }
 
-                // Extruded vertices as the bottom (z = -thickness)
-                let mut top_verts: Vec<Vec3> = points
-                    .iter()
-                    .map(|point| Vec3::new(point.x as f32, point.y as f32, -thickness))
-                    .collect();
-                verts.append(&mut top_verts);
-
-                // now we have to make edges to join the bottom and top vertices.
-                // from here on this was mostly made by grok as I couldn't find any resources on this, and
-                // making the triangles have the exact order you need them to have is hard
-                let mut edge_count: HashMap<(usize, usize), i32> = HashMap::new();
-                for triangle in delaunay.triangles.chunks(3) {
-                    let edges = [
-                        (triangle[0], triangle[1]),
-                        (triangle[1], triangle[2]),
-                        (triangle[2], triangle[0]),
-                    ];
-                    for &(a, b) in edges.iter() {
-                        *edge_count.entry((a, b)).or_insert(0) += 1;
-                        *edge_count.entry((b, a)).or_insert(0) -= 1;
-                    }
-                }
+        let placement = self.placement(transform, global_transform, parent);
+        ShardPlacement {
+            transform: placement.transform * bottom_left(self.size),
+            to_world: placement.to_world * bottom_left(self.size),
+            ..placement
+        }
+    }
 
-                // Only keep edges that appear once (boundary edges)
-                let boundary_edges: Vec<(usize, usize)> = edge_count
+    /// Spawns the shards of `entity` (a glass or another shard), with the given placement and material
+    fn spawn_shards(
+        &self,
+        commands: &mut Commands,
+        meshes: &mut Assets<Mesh>,
+        entity: Entity,
+        mut placement: ShardPlacement,
+        material: &MeshMaterial3d<StandardMaterial>,
+        shards: Vec<FracturedShard>,
+    ) {
+        // the entities are reserved first, so that every shard can know its neighbors
+        let ids: Vec<Entity> = shards.iter().map(|_| commands.spawn_empty().id()).collect();
+        let neighbors = shard_neighbors(&shards);
+        let frame = [self.shape.outline(self.size.xy())];
+
+        // the energy of the impact is shared between all the shards, the ones closest to it get the most
+        if let Some(push) = &mut placement.push {
+            push.share(
+                shards
                     .iter()
-                    .filter(|&(&(_, _), &count)| count == 1)
-                    .map(|(&(a, b), _)| (a, b))
-                    .collect();
-
-                let mut indices: Vec<u32> = Vec::new();
-
-                // Bottom faces (reversed for outward facing)
-                for triangle in delaunay.triangles.chunks(3) {
-                    indices.extend_from_slice(&[
-                        triangle[2] as u32,
-                        triangle[1] as u32,
-                        triangle[0] as u32,
-                    ]);
-                }
+                    .map(|shard| placement.to_world.transform_point(shard.centroid)),
+            );
+        }
 
-                // Top faces
-                for triangle in delaunay.triangles.chunks(3) {
-                    indices.extend_from_slice(&[
-                        (triangle[0] + n) as u32,
-                        (triangle[1] + n) as u32,
-                        (triangle[2] + n) as u32,
-                    ]);
-                }
+        // attached shards are held by the glass instead
+        let attached =
+            self.laminated.is_some() && shards.first().is_some_and(|shard| shard.generation == 0);
+        if let (Some(joints), false) = (&self.joints, attached) {
+            let centroids: Vec<(Entity, Vec3)> = ids
+                .iter()
+                .zip(&shards)
+                .map(|(&id, shard)| (id, shard.centroid))
+                .collect();
+            let scale = placement.to_world.compute_transform().scale;
+            spawn_shard_joints(commands, joints, &centroids, &neighbors, scale);
+        }
 
-                // Side faces with proper winding
-                // TODO: calculate normals here??
-                for &(a, b) in boundary_edges.iter() {
-                    indices.extend_from_slice(&[
-                        a as u32,
-                        b as u32,
-                        (b + n) as u32,
-                        (b + n) as u32,
-                        (a + n) as u32,
-                        a as u32,
-                    ]);
-                }
+        for ((mut shard, &id), neighbors) in shards.into_iter().zip(&ids).zip(neighbors) {
+            shard.neighbors = neighbors.into_iter().map(|i| ids[i]).collect();
+            shard.on_frame = polygons_touch(&shard.polygons, &frame, NEIGHBOR_EPSILON);
 
-                // Create the mesh
-                // I assume I will never need the mesh on the CPU again
-                let mut mesh = Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::RENDER_WORLD,
-                )
-                .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, verts)
-                // .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
-                .with_inserted_indices(Indices::U32(indices));
-
-                // collider
-                let collider =
-                // Collider::trimesh_from_mesh(&mesh) // this has abysmal performance for some reason, but works fine in rapier
-                Collider::convex_hull_from_mesh(&mesh) // this is probably slow to create but is the only way I can get stable performance with avian
-                .expect("Could not make trimesh out of the extrusion mesh for a cell");
-
-                // add the normals. this is VERY inneficient but whatever, had many issues doing it manually
-                // also, should this be done before collider??
-                mesh = mesh.with_duplicated_vertices().with_computed_flat_normals();
-
-                // spawn the glass shard
-                commands.spawn((
-                    shard_transform,
-                    Mesh3d(meshes.add(mesh)),
-                    MeshMaterial3d(glass_material.clone()),
-                    collider,
-                    ShardOf(glass_entity),
-                    Shard {
-                        pos: Vec2::new(shard_center.0 as f32, shard_center.1 as f32),
-                    },
-                ));
+            let mut shard_entity = commands.entity(id);
+            // only the shards of the glass itself are attached to it, the others move like the part of the glass they came from
+            let placement = if attached {
+                shard_entity.insert(AttachedShard);
+                ShardPlacement {
+                    velocity: None,
+                    push: None,
+                    ..placement
+                }
             } else {
-                // delaunay failed
-                warn!("Failed to triangulate a glass shard, skipping it");
-            }
+                placement
+            };
+            spawn_shard(
+                &mut shard_entity,
+                meshes,
+                entity,
+                placement,
+                material,
+                self.physics.as_ref(),
+                shard,
+            );
         }
     }
 
     /// Projects a point onto the glass, returning a position that is relative to the bottom left.
-    /// This is useful since [`Shard`] also uses a relative Vec2 position
+    /// This is useful since [`Shard`] also uses a relative Vec2 position. On a [`GlassCurvature`], it is measured along the curve.
+    /// If the glass has a parent, pass `GlobalTransform::compute_transform` for a point in world space
     pub fn project_to_glass(&self, glass_transf: &Transform, point: Vec3) -> Vec2 {
-        let up = glass_transf.up().as_vec3();
-        let right = glass_transf.right().as_vec3();
-        let half_width = glass_transf.scale.x / 2.0;
-        let half_height = glass_transf.scale.y / 2.0;
-
-        let bottom_left = glass_transf.translation - (right * half_width) - (up * half_height);
-
-        // I make two planes, with an origin at the bottom left of the glass
-        // one of them is pointing to the right, the other is pointing up
-        // this way, for example, by projecting the point to the bottom plane that is pointing up,
-        // I can get how far the point has moved along the width of the glass
-        let isometry = Isometry3d::from_translation(bottom_left);
-        let left_plane = InfinitePlane3d::new(right);
-        let bottom_plane = InfinitePlane3d::new(up);
-
-        let left_proj = left_plane.project_point(isometry, point);
-        let bottom_proj = bottom_plane.project_point(isometry, point);
-
-        Vec2::new(
-            // distance from left to point
-            left_proj.distance(point),
-            // distance from bottom to point
-            bottom_proj.distance(point),
-        )
+        // moving the point into the space of the glass also undoes any scale of the transform,
+        // then it only needs to be offset from the center to the bottom left, where the top face is at z = 0
+        let local = glass_transf
+            .compute_affine()
+            .inverse()
+            .transform_point3(point);
+        let local = bottom_left(self.size)
+            .compute_affine()
+            .inverse()
+            .transform_point3(local);
+
+        // a curved glass is measured along the curve
+        match &self.curvature {
+            Some(curvature) => curvature.unbend(local, self.size.xy()).xy(),
+            None => local.xy(),
+        }
+    }
+}
+
+/// Transform and parent given to the shards when they are spawned
+#[derive(Clone, Copy)]
+struct ShardPlacement {
+    transform: Transform,
+    parent: Option<Entity>,
+    /// From the space of the shard vertices to the world, wherever the shards are placed
+    to_world: GlobalTransform,
+    /// Velocity of the glass (or whatever it is mounted on), given to the shards
+    velocity: Option<BodyVelocity>,
+    /// Push from the [`ShatterImpact`], added on top of the velocity
+    push: Option<ImpactPush>,
+}
+
+/// Spawns a single shard of `entity` into `shard_entity`, see [`Glass::spawn_shards`].
+/// Also used for the shards of a [`SolidGlass`]
+fn spawn_shard(
+    shard_entity: &mut EntityCommands,
+    meshes: &mut Assets<Mesh>,
+    entity: Entity,
+    placement: ShardPlacement,
+    material: &MeshMaterial3d<StandardMaterial>,
+    physics: Option<&ShardPhysics>,
+    shard: FracturedShard,
+) {
+    shard_entity.insert((
+        placement.transform * Transform::from_translation(shard.centroid),
+        Mesh3d(meshes.add(shard.mesh)),
+        MeshMaterial3d(material.0.clone()),
+        ShardOf(entity),
+    ));
+
+    if let Some(parent) = placement.parent {
+        shard_entity.insert(ChildOf(parent));
+    }
+
+    if placement.velocity.is_some() || placement.push.is_some() {
+        let centroid = placement.to_world.transform_point(shard.centroid);
+        let (mut linear, mut angular) = placement
+            .velocity
+            .map_or((Vec3::ZERO, Vec3::ZERO), |velocity| {
+                (velocity.at(centroid), velocity.angular)
+            });
+        if let Some(push) = placement.push {
+            let density = physics.map_or(1.0, |physics| physics.density);
+            let (push_linear, push_angular) =
+                push.velocity(centroid, shard.volume * density, shard.radius);
+            linear += push_linear;
+            angular += push_angular;
+        }
+        shard_entity.insert((LinearVelocity(linear), AngularVelocity(angular)));
+    }
+
+    if let Some(collider) = shard.collider {
+        shard_entity.insert(collider);
+    }
+    if let Some(physics) = physics {
+        shard_entity.insert(physics.bundle());
     }
+
+    // inserted last, so that hooks on Shard can see (and override) everything else
+    shard_entity.insert(Shard {
+        pos: shard.pos,
+        centroid: shard.centroid,
+        outline: shard.outline,
+        area: shard.area,
+        volume: shard.volume,
+        radius: shard.radius,
+        min_angle: shard.min_angle,
+        neighbors: shard.neighbors,
+        on_frame: shard.on_frame,
+        polygons: shard.polygons,
+        generation: shard.generation,
+    });
+}
+
+/// The output of [`Glass::build_shard`], everything needed to spawn a shard
+struct FracturedShard {
+    /// See [`Shard::pos`]
+    pos: Vec2,
+    /// See [`Shard::centroid`]
+    centroid: Vec3,
+    /// See [`Shard::outline`]
+    outline: Vec<Vec2>,
+    /// See [`Shard::area`]
+    area: f32,
+    /// See [`Shard::volume`]
+    volume: f32,
+    /// See [`Shard::radius`]
+    radius: f32,
+    /// See [`Shard::min_angle`]
+    min_angle: f32,
+    /// See [`Shard::neighbors`], filled in by [`Glass::spawn_shards`]
+    neighbors: Vec<Entity>,
+    /// See [`Shard::on_frame`], filled in by [`Glass::spawn_shards`]
+    on_frame: bool,
+    /// See [`Shard::polygons`]
+    polygons: Vec<Vec<Vec2>>,
+    /// See [`Shard::generation`]
+    generation: u32,
+    mesh: Mesh,
+    collider: Option<Collider>,
 }
 
 /// A glass shard is a [`ShardOf`] a certain glass.
 /// This allows you to get information on what glass caused certain shards to spawn.
+/// Shards of a shard that was [`Shattered`] point to that shard instead, see [`Shard::generation`].
 #[derive(Component)]
 #[relationship(relationship_target = Shards)]
 pub struct ShardOf(pub Entity);
@@ -328,44 +754,343 @@ pub struct Shards(Vec<Entity>);
 /// Every glass shard has this component, so you can use it with a hook to customize the shards.
 #[derive(Component)]
 pub struct Shard {
-    /// Position in the glass, relative to the bottom left point.
-    /// Values are negative sometimes, I have no idea how or why
+    /// Position in the glass, relative to the bottom left point, so it is never negative.
+    /// This is the cell point the shard was generated from (see [`SeedGenerator`]),
+    /// or the center of the shard for the [`FractureMode`]s that don't use cell points
     pub pos: Vec2,
+    /// Center of mass of the shard, relative to the bottom left of the glass with the top face at z = 0, so z is minus half the thickness.
+    /// For curved glass, this is after the shard is bent.
+    /// The [`Transform`] of the shard is placed here, and its mesh and collider are centered on it.
+    /// For the shards of a [`SolidGlass`], this is in the space of the mesh of the solid instead
+    pub centroid: Vec3,
+    /// Outline of the shard, counter-clockwise and in the same space as [`Shard::pos`].
+    /// Empty for the shards of a [`SolidGlass`]
+    pub outline: Vec<Vec2>,
+    /// Area of the outline (of one face of the shard). For the shards of a [`SolidGlass`], the area of all of its faces
+    pub area: f32,
+    /// Volume of the shard, which is its mass once multiplied by the density (see [`ShardPhysics::density`])
+    pub volume: f32,
+    /// Distance from the [`Shard::centroid`] to the furthest point of the shard, for quick bounding sphere checks
+    pub radius: f32,
+    /// Smallest angle between two edges of the shard, in radians. The smaller it is, the sharper the shard
+    pub min_angle: f32,
+    /// Shards that share an edge (or a face, for a [`SolidGlass`]) with this one. Only shards from the same shatter are neighbors
+    pub neighbors: Vec<Entity>,
+    /// Whether the shard touches the outline of the glass, where it would be held by a frame. Always false for the shards of a [`SolidGlass`].
+    /// See [`StructuralSupport`]
+    pub on_frame: bool,
+    /// Convex polygons the shard was extruded from, counter-clockwise and in the same space as [`Shard::pos`].
+    /// Empty for the shards of a [`SolidGlass`], which have a [`SolidShard`] instead
+    pub polygons: Vec<Vec<Vec2>>,
+    /// 0 for the shards of a glass, 1 for the shards of a shard of a glass, and so on.
+    /// Shards can be [`Shattered`] again, and their shards are [`ShardOf`] the shard they came from
+    pub generation: u32,
 }
 
-/// Resource created by the plugin with a 1x1x1 cube collider.
-/// Used by [`AutoGlass`], but you can use it too.
-#[derive(Resource)]
-struct GlassCollider(pub Collider);
-
 /// Resource created by the plugin with a 1x1x1 cube mesh.
-/// Used by [`AutoGlass`], but you can use it too.
+/// It matches a glass with a [`Glass::size`] of [`Vec3::ONE`], for any other size use [`Glass::mesh`]
 #[derive(Resource)]
 pub struct GlassMesh(pub Handle<Mesh>);
 
 /// Add this component to an entity with the [`Glass`] component to shatter it,
 /// which creates all the glass shards.
+///
+/// To make the glass break around a certain point, insert a [`ShatterImpact`] together with it.
+///
+/// It can also be added to a [`Shard`], which breaks it into smaller shards using the settings of the glass it came from.
+/// Like with glass, the original shard is left as is, so hide it or despawn it yourself.
+/// Despawning it removes the [`ShardOf`] of its shards
 #[derive(Component)]
 pub struct Shattered;
 
+/// Optional information about where a glass was hit, used when it is [`Shattered`].
+/// Must be inserted before or at the same time as [`Shattered`], for example `commands.entity(glass).insert((Shattered, impact))`.
+///
+/// With the default [`Radial`] seed generator, instead of spreading the cells evenly over the glass, they are packed around the impact point,
+/// so small shards are created near the impact and large ones far away from it. The number of cells stays the same.
+/// Custom [`SeedGenerator`]s also receive it.
+#[derive(Component, Clone, Debug)]
+pub struct ShatterImpact {
+    /// Position of the impact in the glass, relative to the bottom left point. See [`Glass::project_to_glass`].
+    /// For a [`SolidGlass`], it is in the space of the vertices of its mesh, on the z = 0 plane
+    pub point: Vec2,
+    /// Inside this radius the cells are as dense as possible
+    pub radius: f32,
+    /// How fast the density of the cells decreases outside of the radius.
+    /// 0.0 means there is no falloff at all, higher values make the shards far away from the impact larger
+    pub falloff: f32,
+    /// Direction the glass was hit in, in world space. Does not need to be normalized
+    pub direction: Vec3,
+    /// Kinetic energy of the hit, in joules (with avian's default units).
+    /// If it is not 0.0, the shards are pushed along `direction` and away from the impact point, spinning as they go.
+    /// The energy is shared between the shards: the further a shard is from the impact the smaller its share (half as much at `radius`),
+    /// and lighter shards fly faster with the same energy,
+    /// so a small, fast hit like a bullet sprays the shards near the impact while a large `radius` like a body slam pushes the whole glass.
+    /// This is added to the velocity the shards inherit from the glass
+    pub energy: f32,
+}
+
+impl ShatterImpact {
+    /// Creates an impact at a point relative to the bottom left of the glass, with a radius of 1.0 and a quadratic falloff
+    pub fn new(point: Vec2) -> Self {
+        Self {
+            point,
+            radius: 1.0,
+            falloff: 2.0,
+            direction: Vec3::ZERO,
+            energy: 0.0,
+        }
+    }
+
+    /// Sets the direction and energy of the hit, see [`ShatterImpact::energy`]
+    pub fn with_energy(mut self, direction: Vec3, energy: f32) -> Self {
+        self.direction = direction;
+        self.energy = energy;
+        self
+    }
+
+    /// The push given to the shards, if any. `to_world` goes from the space of [`ShatterImpact::point`] to the world
+    pub(crate) fn push(&self, to_world: &GlobalTransform) -> Option<ImpactPush> {
+        let direction = self.direction.try_normalize()?;
+        (self.energy > 0.0).then(|| ImpactPush {
+            point: to_world.transform_point(self.point.extend(0.0)),
+            direction,
+            energy: self.energy,
+            radius: self.radius,
+            // filled in once the shards are known
+            total_weight: 1.0,
+        })
+    }
+}
+
+/// Transforms and parent of an entity, which decide where its shards go. See [`Glass::shard_placement`]
+type PlacementTransforms<'a> = (&'a Transform, &'a GlobalTransform, Option<&'a ChildOf>);
+
+/// A glass, with everything that decides its cells: the impact, the cracks and what is left of it
+type ShatterGlass<'a> = (
+    &'a Glass,
+    PlacementTransforms<'a>,
+    &'a MeshMaterial3d<StandardMaterial>,
+    Option<&'a ShatterImpact>,
+    Option<&'a Cracked>,
+    Option<&'a IntactGlass>,
+    Option<&'a mut ShatterRng>,
+);
+
+/// Entities with [`Glass`] that are shattered by other hooks
+type ShardOrSolid = Or<(With<Shard>, With<SolidGlass>)>;
+
 /// Hook to spawn glass shards when [`Shattered`] is added to a Glass entity
 fn shatter_hook(
     trigger: Trigger<OnAdd, Shattered>,
-    glasses: Populated<(&Glass, &Transform, &MeshMaterial3d<StandardMaterial>)>,
+    others: Query<(), ShardOrSolid>,
+    mut glasses: Populated<ShatterGlass>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    velocities: Velocities,
+) {
+    let entity = trigger.target();
+
+    // shards are handled by shard_shatter_hook, and solids by solid_shatter_hook
+    if others.contains(entity) {
+        return;
+    }
+
+    let (glass, (transform, global_transform, parent), material, impact, cracked, intact, rng) =
+        glasses
+            .get_mut(entity)
+            .expect("Trying to shatter an entity without Glass");
+
+    let mut rng = ShatterRng::or_random(rng);
+
+    let size = glass.size;
+
+    // to allow shard baking, this is now done manually by the user
+    // // mark original entity as invisible
+    // commands.entity(entity).insert(Visibility::Hidden);
+
+    if glass.laminated.is_some() {
+        // the attached shards replace the glass
+        commands.entity(entity).remove::<(Mesh3d, Collider)>();
+    }
+
+    let mut placement = glass.shard_placement(entity, transform, global_transform, parent);
+    placement.velocity = velocities.get(entity);
+    placement.push = impact.and_then(|impact| impact.push(&placement.to_world));
+    let shards: Vec<FracturedShard> = glass
+        .cells(size, impact, cracked, intact, &mut rng)
+        .into_iter()
+        .filter_map(|cell| glass.build_shard(cell, size))
+        .collect();
+    glass.spawn_shards(
+        &mut commands,
+        &mut meshes,
+        entity,
+        placement,
+        material,
+        shards,
+    );
+}
+
+/// A shard, with the glass it came from and whether it is still attached to it
+type ShatterShard<'a> = (
+    &'a Shard,
+    &'a ShardOf,
+    PlacementTransforms<'a>,
+    &'a MeshMaterial3d<StandardMaterial>,
+    Option<&'a ShatterImpact>,
+    Option<&'a mut ShatterRng>,
+    Has<AttachedShard>,
+);
+
+/// Hook to break a shard into smaller shards when [`Shattered`] is added to it.
+/// The shard is fractured like a glass the size of its bounding box, with the cells clipped to its polygons
+fn shard_shatter_hook(
+    trigger: Trigger<OnAdd, Shattered>,
+    mut shards: Populated<ShatterShard, Without<SolidShard>>,
+    parents: Query<&ShardOf>,
+    glasses: Query<&Glass>,
     mut commands: Commands,
-    meshes: ResMut<Assets<Mesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    velocities: Velocities,
 ) {
     let entity = trigger.target();
 
-    let (glass, transform, material) = glasses
-        .get(entity)
-        .expect("Trying to shatter an entity without Glass");
+    let Ok((
+        shard,
+        shard_of,
+        (transform, global_transform, parent),
+        material,
+        impact,
+        rng,
+        attached,
+    )) = shards.get_mut(entity)
+    else {
+        return;
+    };
+
+    // the settings come from the glass this shard (or the shard it came from, and so on) was created from
+    let mut root = shard_of.0;
+    let glass = loop {
+        if let Ok(glass) = glasses.get(root) {
+            break glass;
+        }
+        let Ok(parent) = parents.get(root) else {
+            warn!("Could not find the glass of a shard, it will not be shattered");
+            return;
+        };
+        root = parent.0;
+    };
+
+    let mut rng = ShatterRng::or_random(rng);
+
+    // attached shards are children of the glass, but their shards are not attached to anything
+    let mut placement = if attached {
+        ShardPlacement {
+            transform: global_transform.compute_transform(),
+            parent: None,
+            to_world: *global_transform,
+            velocity: None,
+            push: None,
+        }
+    } else {
+        glass.placement(transform, global_transform, parent)
+    };
+    placement.velocity = velocities.get(entity);
+
+    // the polygons (and the vertices of the new shards) are relative to the glass, not to the center of this shard
+    let offset = Transform::from_translation(-shard.centroid);
+    placement.transform = placement.transform * offset;
+    placement.to_world = placement.to_world * offset;
+    placement.push = impact.and_then(|impact| impact.push(&placement.to_world));
+
+    // everything is done relative to the bottom left of the shard's bounding box, and moved back afterwards
+    let points = shard.polygons.iter().flatten().copied();
+    let min = points.clone().fold(Vec2::MAX, Vec2::min);
+    let max = points.fold(Vec2::MIN, Vec2::max);
+    let glass_size = glass.size;
+    let size = (max - min).extend(glass_size.z);
+
+    let outline = IntactGlass {
+        polygons: shard
+            .polygons
+            .iter()
+            .map(|polygon| polygon.iter().map(|&point| point - min).collect())
+            .collect(),
+    };
+    let impact = impact.map(|impact| ShatterImpact {
+        point: impact.point - min,
+        ..impact.clone()
+    });
+
+    // keep the same density of cells as the glass,
+    // but small shards would get less than the 3 points a voronoi diagram needs
+    let density = glass.num_cell_points.as_vec2() / glass_size.xy();
+    let mut num_cell_points = (density * size.xy()).ceil().as_uvec2().max(UVec2::ONE);
+    if num_cell_points.element_product() < 3 {
+        num_cell_points = UVec2::splat(2);
+    }
+    let shard_glass = Glass {
+        num_cell_points,
+        ..glass.clone()
+    };
+
+    let mut new_shards: Vec<FracturedShard> = Vec::new();
+    for mut cell in shard_glass.cells(size, impact.as_ref(), None, Some(&outline), &mut rng) {
+        cell.pos += min;
+        for point in cell.polygons.iter_mut().flatten() {
+            *point += min;
+        }
 
-    glass.shatter(
+        if let Some(mut new_shard) = shard_glass.build_shard(cell, glass_size) {
+            new_shard.generation = shard.generation + 1;
+            new_shards.push(new_shard);
+        }
+    }
+    shard_glass.spawn_shards(
+        &mut commands,
+        &mut meshes,
         entity,
-        transform,
-        material.0.clone(),
-        commands.reborrow(),
-        meshes,
+        placement,
+        material,
+        new_shards,
     );
 }
+
+// shards (and the frame) that share an edge at least this long touch each other
+const NEIGHBOR_EPSILON: f32 = 0.001;
+
+/// For each shard, the indices of the shards it shares an edge with
+fn shard_neighbors(shards: &[FracturedShard]) -> Vec<Vec<usize>> {
+    let bounds: Vec<Rect> = shards
+        .iter()
+        .map(|shard| {
+            let points = shard.polygons.iter().flatten().copied();
+            Rect::from_corners(
+                points.clone().fold(Vec2::MAX, Vec2::min),
+                points.fold(Vec2::MIN, Vec2::max),
+            )
+        })
+        .collect();
+
+    let mut neighbors: Vec<Vec<usize>> = vec![Vec::new(); shards.len()];
+    for i in 0..shards.len() {
+        for j in (i + 1)..shards.len() {
+            // most shards are far away from each other, which is much cheaper to check
+            let close = !bounds[i]
+                .inflate(NEIGHBOR_EPSILON)
+                .intersect(bounds[j])
+                .is_empty();
+            if close && polygons_touch(&shards[i].polygons, &shards[j].polygons, NEIGHBOR_EPSILON) {
+                neighbors[i].push(j);
+                neighbors[j].push(i);
+            }
+        }
+    }
+    neighbors
+}
+
+/// Offset from the center of a glass of the given size to its bottom left, on the top face (see [`extrude_polygons`])
+fn bottom_left(size: Vec3) -> Transform {
+    Transform::from_translation(Vec3::new(-size.x, -size.y, size.z) / 2.0)
+}
@@ -0,0 +1,277 @@
+use bevy::prelude::*;
+
+use crate::{geometry::*, *};
+
+/// Turns an entity with an existing flat [`Mesh3d`], such as a window pane from a glTF scene, into a [`Glass`].
+/// Requires [`ShatterPlugin`] to have been added. If the mesh is not loaded yet, the glass is built as soon as it is.
+///
+/// The plane, outline (with holes) and thickness of the mesh are detected with [`Glass::from_mesh`].
+/// A copy of the mesh is moved so that the glass is centered on the entity, which looks exactly the same as before,
+/// and the [`Transform`] of the entity is changed to match.
+///
+/// Removed components:
+/// - [`MeshGlass`]
+///
+/// Added components:
+/// - [`Glass`], with the settings of [`MeshGlass::glass`] and the detected shape
+/// - [`Transform`], moved to the center of the glass
+/// - [`Mesh3d`], a moved copy of the original mesh
+/// - [`Collider`], matching the detected shape
+/// - [`MeshGlassSource`], the original mesh and transform
+#[derive(Component, Debug)]
+pub struct MeshGlass {
+    /// Settings for the glass. Its shape, holes and number of cell points are replaced by the detected ones
+    pub glass: Glass,
+    /// Number of cell points per unit of width and height, see [`Glass::new_with_density`]
+    pub cells_per_unit: f32,
+}
+
+/// The mesh and transform an entity had before [`MeshGlass`] turned it into glass.
+/// The original mesh is left untouched, so this can be used to restore the entity
+#[derive(Component, Clone, Debug)]
+pub struct MeshGlassSource {
+    /// The original mesh, not moved
+    pub mesh: Handle<Mesh>,
+    /// The original transform of the entity
+    pub transform: Transform,
+}
+
+impl MeshGlass {
+    /// Detects the glass from the mesh of the entity, with the settings of `glass` and `cells_per_unit` cell points per unit
+    pub fn new(glass: Glass, cells_per_unit: f32) -> Self {
+        Self {
+            glass,
+            cells_per_unit,
+        }
+    }
+}
+
+impl Glass {
+    /// Meshes without any thickness (like a single quad) get this thickness instead
+    pub const MIN_MESH_THICKNESS: f32 = 0.01;
+
+    /// Detects a glass from a flat mesh: its plane, the outline of its top face (with holes) and its thickness.
+    /// Curved meshes are not supported, they are treated as flat.
+    ///
+    /// Returns the glass and a transform from the glass (which is centered on its entity) to the vertices of the mesh.
+    /// Returns `None` if the mesh is not a triangle list or is empty
+    pub fn from_mesh(mesh: &Mesh, cells_per_unit: f32) -> Option<(Self, Transform)> {
+        let triangles = solid::mesh_triangles(mesh)?;
+        let normals: Vec<Vec3> = triangles
+            .iter()
+            .map(|[a, b, c]| (*b - *a).cross(*c - *a))
+            .collect();
+
+        // the biggest triangle is on the top or the bottom face, every triangle facing the same way refines the normal
+        let largest = normals
+            .iter()
+            .copied()
+            .max_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))?
+            .try_normalize()?;
+        let facing = |normal: Vec3| normal.normalize_or_zero().dot(largest) > FACING_COS;
+        let normal = normals
+            .iter()
+            .copied()
+            .filter(|&normal| facing(normal))
+            .sum::<Vec3>()
+            .try_normalize()?;
+
+        // keep the width along the X axis of the mesh when possible, since most panes are modeled that way
+        let right = (Vec3::X - normal * normal.x)
+            .try_normalize()
+            .or_else(|| (Vec3::Y - normal * normal.y).try_normalize())?;
+        let up = normal.cross(right);
+        let project = |point: Vec3| Vec2::new(point.dot(right), point.dot(up));
+
+        let depths = triangles.iter().flatten().map(|point| point.dot(normal));
+        let top = depths.clone().fold(f32::MIN, f32::max);
+        let bottom = depths.fold(f32::MAX, f32::min);
+        let thickness = (top - bottom).max(Self::MIN_MESH_THICKNESS);
+
+        // the outline of the face is counter-clockwise, holes are clockwise
+        let top_face: Vec<Vec<Vec2>> = triangles
+            .iter()
+            .zip(&normals)
+            .filter(|(_, normal)| facing(**normal))
+            .map(|(triangle, _)| triangle.map(project).to_vec())
+            .collect();
+        let mut loops = boundary_loops(&top_face);
+
+        // the outline is the biggest loop, every other one is a hole
+        let (outline_index, _) = loops
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| polygon_area(a).abs().total_cmp(&polygon_area(b).abs()))?;
+        let outline = loops.swap_remove(outline_index);
+
+        let min = outline.iter().copied().fold(Vec2::MAX, Vec2::min);
+        let max = outline.iter().copied().fold(Vec2::MIN, Vec2::max);
+        let size = max - min;
+        if size.min_element() <= f32::EPSILON {
+            return None;
+        }
+
+        let mut glass = Glass::new_with_density(size.x, size.y, cells_per_unit)
+            .with_thickness(thickness)
+            .with_shape(GlassShape::Polygon(outline));
+        for hole in loops {
+            let hole_min = hole.iter().copied().fold(Vec2::MAX, Vec2::min);
+            let hole_max = hole.iter().copied().fold(Vec2::MIN, Vec2::max);
+            glass = glass.with_hole(GlassHole::new(
+                GlassShape::Polygon(hole),
+                Rect::from_corners((hole_min - min) / size, (hole_max - min) / size),
+            ));
+        }
+
+        let center = (min + max) / 2.0;
+        let frame = Transform {
+            translation: right * center.x + up * center.y + normal * (top - thickness / 2.0),
+            rotation: Quat::from_mat3(&Mat3::from_cols(right, up, normal)),
+            ..default()
+        };
+
+        Some((glass, frame))
+    }
+}
+
+// triangles whose normal is within ~25 degrees of the glass normal are part of its top face
+const FACING_COS: f32 = 0.9;
+
+/// The mesh to detect the glass from, and the transform to move along with it
+type MeshGlassParts<'a> = (Entity, &'a MeshGlass, &'a Mesh3d, &'a Transform);
+
+/// Turns the entities with [`MeshGlass`] into glass once their mesh is loaded, which can take a few frames for glTF scenes
+pub(super) fn build_mesh_glasses(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    asset_server: Res<AssetServer>,
+    mesh_glasses: Populated<MeshGlassParts>,
+) {
+    for (entity, mesh_glass, mesh3d, transform) in mesh_glasses.iter() {
+        let Some(mesh) = meshes.get(&mesh3d.0) else {
+            if asset_server.load_state(&mesh3d.0).is_failed() {
+                warn!("The mesh of a MeshGlass failed to load");
+                commands.entity(entity).remove::<MeshGlass>();
+            }
+            continue;
+        };
+        let Some((detected, frame)) = Glass::from_mesh(mesh, mesh_glass.cells_per_unit) else {
+            warn!("Could not find a flat glass in the mesh of a MeshGlass");
+            commands.entity(entity).remove::<MeshGlass>();
+            continue;
+        };
+
+        let glass = Glass {
+            num_cell_points: detected.num_cell_points,
+            size: detected.size,
+            shape: detected.shape,
+            holes: detected.holes,
+            ..mesh_glass.glass.clone()
+        };
+
+        // the mesh may be shared with other entities, so a moved copy centered on the glass is used instead,
+        // and the new transform moves it back
+        let inverse = Transform::from_rotation(frame.rotation.inverse())
+            * Transform::from_translation(-frame.translation);
+        let mesh = mesh.clone().transformed_by(inverse);
+
+        let mut entitycmd = commands.entity(entity);
+        if let Some(collider) = glass.collider() {
+            entitycmd.insert(collider);
+        }
+        entitycmd.insert((
+            MeshGlassSource {
+                mesh: mesh3d.0.clone(),
+                transform: *transform,
+            },
+            *transform * frame,
+            Mesh3d(meshes.add(mesh)),
+            glass,
+        ));
+        entitycmd.remove::<MeshGlass>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    /// Detects the glass of a mesh, checking its size and that the frame puts the glass where the mesh is
+    fn detect(mesh: Mesh, center: Vec3, normal: Vec3, size: Vec3) -> Glass {
+        let (glass, frame) = Glass::from_mesh(&mesh, 4.0).expect("no glass detected");
+
+        assert!(
+            glass.size.abs_diff_eq(size, 1e-4),
+            "detected a size of {}",
+            glass.size
+        );
+        assert!(frame.translation.abs_diff_eq(center, 1e-4));
+        // the glass can face either side of the mesh
+        assert!((frame.rotation * Vec3::Z).dot(normal).abs() > 0.9999);
+        glass
+    }
+
+    #[test]
+    fn detects_boxes() {
+        detect(
+            Cuboid::new(2.0, 1.0, 0.1).mesh().build(),
+            Vec3::ZERO,
+            Vec3::Z,
+            Vec3::new(2.0, 1.0, 0.1),
+        );
+
+        // the width stays along the X axis of the mesh
+        let rotation = Quat::from_rotation_x(0.7);
+        detect(
+            Cuboid::new(2.0, 1.0, 0.1)
+                .mesh()
+                .build()
+                .rotated_by(rotation)
+                .translated_by(Vec3::new(1.0, 2.0, 3.0)),
+            Vec3::new(1.0, 2.0, 3.0),
+            rotation * Vec3::Z,
+            Vec3::new(2.0, 1.0, 0.1),
+        );
+
+        // without an X axis to follow, the width goes along Y
+        detect(
+            Cuboid::new(0.05, 2.0, 1.0).mesh().build(),
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::new(2.0, 1.0, 0.05),
+        );
+    }
+
+    #[test]
+    fn detects_planes() {
+        let plane = Plane3d::new(Vec3::Y, Vec2::new(1.5, 0.5)).mesh().build();
+        let glass = detect(
+            plane,
+            Vec3::new(0.0, -Glass::MIN_MESH_THICKNESS / 2.0, 0.0),
+            Vec3::Y,
+            Vec3::new(3.0, 1.0, Glass::MIN_MESH_THICKNESS),
+        );
+        assert!(glass.holes.is_empty());
+    }
+
+    #[test]
+    fn detects_holes() {
+        let hole = GlassHole::new(GlassShape::Rectangle, Rect::new(0.25, 0.5, 0.75, 0.75));
+        let mesh = Glass::new(UVec2::splat(4), Vec3::new(2.0, 1.0, 0.1))
+            .with_hole(hole.clone())
+            .mesh()
+            .unwrap();
+
+        let glass = detect(mesh, Vec3::ZERO, Vec3::Z, Vec3::new(2.0, 1.0, 0.1));
+        assert_eq!(glass.holes.len(), 1);
+        // the glass is upside down if it faces the bottom of the mesh
+        let rect = glass.holes[0].rect;
+        let flipped = Rect::new(0.25, 0.25, 0.75, 0.5);
+        assert!(
+            [hole.rect, flipped].iter().any(|expected| {
+                rect.min.abs_diff_eq(expected.min, 1e-4) && rect.max.abs_diff_eq(expected.max, 1e-4)
+            }),
+            "detected the hole at {rect:?}"
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{geometry::*, *};
+
+/// Add this component to an entity with the [`Glass`] component to only shatter the part of it around the [`ShatterImpact`], like a bullet hole.
+/// The cell that was hit and the cells within [`ShatterImpact::radius`] of the impact become shards, and the rest of the glass is rebuilt as a single mesh with a hole in it.
+/// If the impact misses what is left of the glass, nothing happens.
+/// The [`Mesh3d`] and [`Collider`] of the glass are replaced with the new ones, if it has them.
+///
+/// Unlike [`Shattered`], this is removed as soon as the glass is rebuilt, so it can be inserted again for the next hit.
+/// What is left of the glass is kept in [`IntactGlass`], so any later shatter (partial or not) only breaks what is left.
+///
+/// If there is no [`ShatterImpact`], the glass is hit at its center with [`ShatterImpact::new`]
+#[derive(Component)]
+pub struct PartialShatter;
+
+/// What is left of a glass after a [`PartialShatter`]. Added to the glass automatically, glass without it is still whole.
+#[derive(Component, Clone, Debug, Default)]
+pub struct IntactGlass {
+    /// Convex polygons, counter-clockwise and relative to the bottom left of the glass.
+    /// These are the cells that did not break, so they can be used to place effects along the edge of the hole
+    pub polygons: Vec<Vec<Vec2>>,
+}
+
+impl IntactGlass {
+    /// Whether nothing is left of the glass
+    pub fn is_empty(&self) -> bool {
+        self.polygons.is_empty()
+    }
+
+    /// Clips cells to what is left of the glass, dropping the ones that are completely gone
+    pub(crate) fn clip(&self, cells: Vec<Cell>, epsilon: f32) -> Vec<Cell> {
+        cells
+            .into_iter()
+            .filter_map(|cell| {
+                // every intact polygon is convex, and so is every cell, so this never needs anything fancier than Sutherland-Hodgman
+                let polygons: Vec<Vec<Vec2>> = cell
+                    .polygons
+                    .iter()
+                    .flat_map(|polygon| {
+                        self.polygons
+                            .iter()
+                            .map(|intact| clip_polygon(polygon, intact, epsilon))
+                    })
+                    .filter(|polygon| {
+                        polygon.len() >= 3 && polygon_area(polygon).abs() > epsilon * epsilon
+                    })
+                    .collect();
+
+                (!polygons.is_empty()).then_some(Cell {
+                    pos: cell.pos,
+                    polygons,
+                })
+            })
+            .collect()
+    }
+
+    /// Mesh of what is left of the glass. Like [`Glass::mesh`], it is centered on the glass entity
+    fn mesh(&self, size: Vec3, curvature: Option<&GlassCurvature>) -> Option<Mesh> {
+        outline_mesh(&self.polygons, size, curvature)
+    }
+
+    /// Collider of what is left of the glass, one convex hull per polygon. Also centered on the glass entity
+    fn collider(&self, size: Vec3, curvature: Option<&GlassCurvature>) -> Option<Collider> {
+        outline_collider(&self.polygons, size, curvature)
+    }
+}
+
+/// Like the glass of [`shatter_hook`], plus whether it has a mesh and a collider to rebuild
+type PartialGlass<'a> = (
+    &'a Glass,
+    PlacementTransforms<'a>,
+    &'a MeshMaterial3d<StandardMaterial>,
+    Option<&'a ShatterImpact>,
+    Option<&'a Cracked>,
+    Option<&'a IntactGlass>,
+    Option<&'a mut ShatterRng>,
+    Has<Mesh3d>,
+    Has<Collider>,
+);
+
+/// Hook to punch a hole in the glass when [`PartialShatter`] is added to a Glass entity
+pub(super) fn partial_shatter_hook(
+    trigger: Trigger<OnAdd, PartialShatter>,
+    mut glasses: Populated<PartialGlass>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    velocities: Velocities,
+) {
+    let entity = trigger.target();
+
+    let (
+        glass,
+        (transform, global_transform, parent),
+        material,
+        impact,
+        cracked,
+        intact,
+        rng,
+        has_mesh,
+        has_collider,
+    ) = glasses
+        .get_mut(entity)
+        .expect("Trying to shatter an entity without Glass");
+
+    let mut rng = ShatterRng::or_random(rng);
+
+    let size = glass.size;
+    let center = ShatterImpact::new(size.xy() / 2.0);
+    let impact = impact.unwrap_or(&center);
+
+    // the whole glass is fractured as usual, and only the cells near the impact are turned into shards.
+    // The cell that was hit always breaks, even if the radius is too small to reach any cell point
+    let (broken, remaining): (Vec<Cell>, Vec<Cell>) = glass
+        .cells(size, Some(impact), cracked, intact, &mut rng)
+        .into_iter()
+        .partition(|cell| {
+            cell.pos.distance(impact.point) <= impact.radius
+                || cell
+                    .polygons
+                    .iter()
+                    .any(|polygon| convex_contains(polygon, impact.point))
+        });
+
+    // missed the glass entirely, for example by hitting an existing hole
+    if broken.is_empty() {
+        commands.entity(entity).remove::<PartialShatter>();
+        return;
+    }
+
+    let mut placement = glass.shard_placement(entity, transform, global_transform, parent);
+    placement.velocity = velocities.get(entity);
+    placement.push = impact.push(&placement.to_world);
+    let shards: Vec<FracturedShard> = broken
+        .into_iter()
+        .filter_map(|cell| glass.build_shard(cell, size))
+        .collect();
+    glass.spawn_shards(
+        &mut commands,
+        &mut meshes,
+        entity,
+        placement,
+        material,
+        shards,
+    );
+
+    let intact = IntactGlass {
+        polygons: remaining
+            .into_iter()
+            .flat_map(|cell| cell.polygons)
+            // clipping against these later needs them to be counter-clockwise
+            .map(counter_clockwise)
+            .collect(),
+    };
+
+    let mut glass_entity = commands.entity(entity);
+    glass_entity.remove::<PartialShatter>();
+
+    if has_mesh {
+        match intact.mesh(size, glass.curvature.as_ref()) {
+            Some(mesh) => {
+                glass_entity.insert(Mesh3d(meshes.add(mesh)));
+            }
+            None => {
+                glass_entity.remove::<Mesh3d>();
+            }
+        }
+    }
+
+    if has_collider {
+        match intact.collider(size, glass.curvature.as_ref()) {
+            Some(collider) => {
+                glass_entity.insert(collider);
+            }
+            None => {
+                glass_entity.remove::<Collider>();
+            }
+        }
+    }
+
+    glass_entity.insert(intact);
+}
@@ -0,0 +1,222 @@
+use avian3d::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*, render::mesh::MeshAabb};
+
+/// Which collider is given to each shard
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShardCollider {
+    /// Convex hull of the shard, matches the mesh exactly
+    #[default]
+    ConvexHull,
+    /// Box around the shard. Much cheaper than [`ShardCollider::ConvexHull`], good enough for small shards
+    Cuboid,
+    /// No collider at all, for example for purely visual debris
+    None,
+}
+
+impl ShardCollider {
+    /// Builds the collider of a shard from its mesh
+    pub(crate) fn build(&self, mesh: &Mesh) -> Option<Collider> {
+        match self {
+            ShardCollider::ConvexHull => {
+                // the mesh has duplicated vertices for flat shading, so it has no indices to build the hull from
+                let points = mesh
+                    .attribute(Mesh::ATTRIBUTE_POSITION)?
+                    .as_float3()?
+                    .iter()
+                    .map(|&point| Vec3::from(point))
+                    .collect();
+                let collider = Collider::convex_hull(points);
+                if collider.is_none() {
+                    warn!("Could not make a convex hull out of the extrusion mesh for a cell");
+                }
+                collider
+            }
+            ShardCollider::Cuboid => {
+                let aabb = mesh.compute_aabb()?;
+                let size = Vec3::from(aabb.half_extents) * 2.0;
+
+                // the shard's transform is at its center of mass, which is not the center of its bounding box, so the box still has to be offset
+                Some(Collider::compound(vec![(
+                    Position(aabb.center.into()),
+                    Rotation::default(),
+                    Collider::cuboid(size.x, size.y, size.z),
+                )]))
+            }
+            ShardCollider::None => None,
+        }
+    }
+}
+
+/// Physics material given to each shard. Shards of glass without this use avian's defaults
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShardPhysics {
+    /// See [`Friction`]
+    pub friction: f32,
+    /// See [`Restitution`]
+    pub restitution: f32,
+    /// See [`ColliderDensity`]. Avian's default is 1.0, glass is about 2.5 times as dense as water
+    pub density: f32,
+}
+
+impl Default for ShardPhysics {
+    fn default() -> Self {
+        Self {
+            friction: 0.4,
+            restitution: 0.1,
+            density: 2.5,
+        }
+    }
+}
+
+impl ShardPhysics {
+    /// The components to insert into each shard
+    pub(crate) fn bundle(&self) -> impl Bundle {
+        (
+            Friction::new(self.friction),
+            Restitution::new(self.restitution),
+            ColliderDensity(self.density),
+        )
+    }
+}
+
+/// Linear and angular velocity of a rigid body, used to give the shards the same motion as the glass they came from
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct BodyVelocity {
+    pub linear: Vec3,
+    pub angular: Vec3,
+    /// Center of mass of the body, in world space
+    pub center: Vec3,
+}
+
+impl BodyVelocity {
+    /// Velocity of a point of the body, in world space
+    pub fn at(&self, point: Vec3) -> Vec3 {
+        self.linear + self.angular.cross(point - self.center)
+    }
+}
+
+/// Finds the velocity of an entity, which is its own if it is a moving rigid body,
+/// or the one of its closest ancestor that is (such as the car a window is mounted on)
+#[derive(SystemParam)]
+pub(crate) struct Velocities<'w, 's> {
+    bodies: Query<
+        'w,
+        's,
+        (
+            &'static RigidBody,
+            &'static LinearVelocity,
+            &'static AngularVelocity,
+            &'static GlobalTransform,
+            Option<&'static ComputedCenterOfMass>,
+        ),
+    >,
+    parents: Query<'w, 's, &'static ChildOf>,
+}
+
+impl Velocities<'_, '_> {
+    pub fn get(&self, entity: Entity) -> Option<BodyVelocity> {
+        let mut current = entity;
+        loop {
+            // static glass does not move, but whatever it is mounted on might
+            if let Ok((body, linear, angular, transform, center_of_mass)) = self.bodies.get(current)
+                && !body.is_static()
+            {
+                let center = center_of_mass.map_or(Vec3::ZERO, |center| center.0);
+                return Some(BodyVelocity {
+                    linear: linear.0,
+                    angular: angular.0,
+                    center: transform.transform_point(center),
+                });
+            }
+            current = self.parents.get(current).ok()?.parent();
+        }
+    }
+}
+
+/// How much the shards spread out from the impact, compared to how much they follow its direction
+const PUSH_SPREAD: f32 = 0.5;
+/// How much the shards spin, compared to how fast they move
+const PUSH_SPIN: f32 = 0.5;
+
+/// The push a [`crate::ShatterImpact`] with some energy gives to the shards, in world space
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ImpactPush {
+    pub point: Vec3,
+    /// Normalized
+    pub direction: Vec3,
+    pub energy: f32,
+    pub radius: f32,
+    /// Sum of the [`ImpactPush::weight`] of every shard, so that they share the energy between them
+    pub total_weight: f32,
+}
+
+impl ImpactPush {
+    /// How much of the energy a shard with the given center (in world space) gets, compared to the others.
+    /// Falls off with the distance to the impact, a shard at `radius` gets half as much as one at the impact
+    pub fn weight(&self, center: Vec3) -> f32 {
+        1.0 / (1.0
+            + center.distance_squared(self.point) / (self.radius * self.radius).max(f32::EPSILON))
+    }
+
+    /// Shares the energy between shards with the given centers (in world space), see [`ImpactPush::total_weight`]
+    pub fn share(&mut self, centers: impl IntoIterator<Item = Vec3>) {
+        self.total_weight = centers.into_iter().map(|center| self.weight(center)).sum();
+    }
+
+    /// Linear and angular velocity of a shard with the given center (in world space), mass and radius.
+    /// Its share of the energy depends on its [`ImpactPush::weight`], and lighter shards fly faster
+    pub fn velocity(&self, center: Vec3, mass: f32, radius: f32) -> (Vec3, Vec3) {
+        let offset = center - self.point;
+        let energy = self.energy * self.weight(center) / self.total_weight.max(f32::EPSILON);
+        let speed = (2.0 * energy / mass.max(f32::EPSILON)).sqrt();
+
+        // out the other side of the glass, spreading away from the impact
+        let outwards = offset
+            .reject_from_normalized(self.direction)
+            .normalize_or_zero();
+        let linear = (self.direction + outwards * PUSH_SPREAD).normalize() * speed;
+
+        // tumbling forwards, like the edge closest to the impact was pushed
+        let angular = self.direction.cross(outwards) * speed * PUSH_SPIN / radius.max(0.01);
+
+        (linear, angular)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn shards_get_a_collider() {
+        let circle = GlassShape::Circle(Circle::new(1.0));
+        let glasses = [
+            Glass::new(UVec2::new(4, 4), Vec3::new(2.0, 1.0, 0.1)),
+            Glass::from_kind(GlassKind::Laminated, 2.0, 2.0).with_shape(circle),
+            Glass::new_with_density(3.0, 2.0, 3.0)
+                .with_curvature(GlassCurvature::Cylindrical { radius: 2.0 }),
+            Glass::new_with_density(3.0, 2.0, 3.0).with_collider(ShardCollider::Cuboid),
+        ];
+
+        for glass in glasses {
+            for seed in 0..5 {
+                let cells = glass.cells(
+                    glass.size,
+                    None,
+                    None,
+                    None,
+                    &mut ShatterRng::with_seed(seed),
+                );
+                assert!(!cells.is_empty());
+                for cell in cells {
+                    let shard = glass.build_shard(cell, glass.size).expect("no shard built");
+                    assert!(
+                        shard.collider.is_some(),
+                        "no collider for {:?}",
+                        glass.collider
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use avian3d::prelude::Collider;
+use avian3d::prelude::PhysicsSet;
 use bevy::prelude::*;
 
 use crate::*;
@@ -10,11 +10,30 @@ impl Plugin for ShatterPlugin {
     fn build(&self, app: &mut App) {
         let mut meshes = app.world_mut().resource_mut::<Assets<Mesh>>();
         let glass_mesh = meshes.add(Cuboid::new(1.0, 1.0, 1.0));
-        let glass_collider = Collider::cuboid(1.0, 1.0, 1.0);
 
         app.insert_resource(GlassMesh(glass_mesh))
-            .insert_resource(GlassCollider(glass_collider))
+            .init_resource::<RectangleGlasses>()
             .add_observer(autoglass_hook)
-            .add_observer(shatter_hook);
+            .add_observer(shatter_hook)
+            .add_observer(shard_shatter_hook)
+            .add_observer(partial_shatter_hook)
+            .add_observer(solid_shatter_hook)
+            .add_observer(glass_hit_observer)
+            .add_systems(
+                FixedPostUpdate,
+                (
+                    release_attached_shards,
+                    break_shard_joints,
+                    shatter_on_impact,
+                )
+                    .after(PhysicsSet::StepSimulation),
+            )
+            .add_systems(
+                Update,
+                (
+                    build_mesh_glasses,
+                    (schedule_shard_release, release_shards, update_shard_support).chain(),
+                ),
+            );
     }
 }
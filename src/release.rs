@@ -0,0 +1,86 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::*;
+
+/// Add this to a [`Glass`] to make its shards fall in waves once it is [`Shattered`], starting at the [`ShatterImpact`]
+/// (or the center of the glass) and spreading outwards, like a real window that breaks and then crumbles.
+/// Requires [`ShatterPlugin`] to have been added.
+///
+/// The shards are spawned with a [`RigidBody::Static`] (unless they already have a rigid body) and a [`PendingRelease`],
+/// and become [`RigidBody::Dynamic`] when their time comes.
+/// Shards further than `max_radius` from the impact are never released, so they stay in the frame
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct ShardRelease {
+    /// How fast the release spreads from the impact, in units per second
+    pub speed: f32,
+    /// Each shard is released up to this many seconds later, at random, so the waves are not perfect circles
+    pub jitter: f32,
+    /// Shards whose center is further than this from the impact are never released
+    pub max_radius: f32,
+}
+
+impl Default for ShardRelease {
+    fn default() -> Self {
+        Self {
+            speed: 4.0,
+            jitter: 0.2,
+            max_radius: 2.0,
+        }
+    }
+}
+
+/// A shard that will become [`RigidBody::Dynamic`] at some point, see [`ShardRelease`]
+#[derive(Component, Clone, Copy, Debug)]
+pub struct PendingRelease {
+    /// Elapsed [`Time`] at which the shard is released, in seconds
+    pub at: f32,
+}
+
+/// Schedules the release of the new shards of every glass with [`ShardRelease`]
+pub(super) fn schedule_shard_release(
+    new_shards: Query<(Entity, &Shard, &ShardOf), Added<Shard>>,
+    mut glasses: Query<(
+        &Glass,
+        &ShardRelease,
+        Option<&ShatterImpact>,
+        Option<&mut ShatterRng>,
+    )>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, shard, shard_of) in new_shards.iter() {
+        let Ok((glass, release, impact, rng)) = glasses.get_mut(shard_of.0) else {
+            continue;
+        };
+        let mut rng = ShatterRng::or_random(rng);
+
+        let point = impact.map_or(glass.size.xy() / 2.0, |impact| impact.point);
+        let distance = shard.centroid.xy().distance(point);
+
+        let mut shard_entity = commands.entity(entity);
+        shard_entity.insert_if_new(RigidBody::Static);
+        if distance <= release.max_radius {
+            let delay = distance / release.speed.max(f32::EPSILON) + rng.f32() * release.jitter;
+            shard_entity.insert(PendingRelease {
+                at: time.elapsed_secs() + delay,
+            });
+        }
+    }
+}
+
+/// Makes the shards dynamic once their [`PendingRelease`] time comes
+pub(super) fn release_shards(
+    shards: Query<(Entity, &PendingRelease)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, pending) in shards.iter() {
+        if time.elapsed_secs() >= pending.at {
+            commands
+                .entity(entity)
+                .remove::<PendingRelease>()
+                .insert(RigidBody::Dynamic);
+        }
+    }
+}
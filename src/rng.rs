@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+
+/// The random number generator used when shattering a glass.
+///
+/// Add it to a glass entity to make shattering deterministic: the same seed, glass and [`ShatterImpact`](crate::ShatterImpact)
+/// always produce the same shards, which is useful for replays, lockstep multiplayer and tests.
+/// The generator is advanced every time the glass is shattered.
+///
+/// Glass without this component uses a randomly seeded generator.
+/// To use an entropy source such as `bevy_rand`, just take a `u64` from it and pass it to [`ShatterRng::with_seed`].
+#[derive(Component, Clone, Debug, Deref, DerefMut)]
+pub struct ShatterRng(pub fastrand::Rng);
+
+impl ShatterRng {
+    /// Creates a generator with a fixed seed
+    pub fn with_seed(seed: u64) -> Self {
+        Self(fastrand::Rng::with_seed(seed))
+    }
+
+    /// The generator of an entity, or a randomly seeded one if it has none
+    pub(crate) fn or_random(rng: Option<Mut<'_, ShatterRng>>) -> EntityRng<'_> {
+        match rng {
+            Some(rng) => EntityRng::Entity(rng),
+            None => EntityRng::Random(ShatterRng::default()),
+        }
+    }
+}
+
+impl Default for ShatterRng {
+    /// Creates a randomly seeded generator
+    fn default() -> Self {
+        Self(fastrand::Rng::new())
+    }
+}
+
+/// See [`ShatterRng::or_random`]
+pub(crate) enum EntityRng<'a> {
+    Entity(Mut<'a, ShatterRng>),
+    Random(ShatterRng),
+}
+
+impl std::ops::Deref for EntityRng<'_> {
+    type Target = ShatterRng;
+
+    fn deref(&self) -> &ShatterRng {
+        match self {
+            EntityRng::Entity(rng) => rng,
+            EntityRng::Random(rng) => rng,
+        }
+    }
+}
+
+impl std::ops::DerefMut for EntityRng<'_> {
+    fn deref_mut(&mut self) -> &mut ShatterRng {
+        match self {
+            EntityRng::Entity(rng) => rng,
+            EntityRng::Random(rng) => rng,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    /// Shatters the glass twice with the same seed, returning the cells of each time
+    fn shatter_twice(glass: &Glass, impact: Option<&ShatterImpact>) -> [Vec<Cell>; 2] {
+        [0, 1].map(|_| {
+            let mut rng = ShatterRng::with_seed(42);
+            glass.cells(glass.size, impact, None, None, &mut rng)
+        })
+    }
+
+    #[test]
+    fn same_seed_same_cells() {
+        let impact = ShatterImpact::new(Vec2::new(1.5, 0.5));
+        let modes = [
+            FractureMode::Voronoi,
+            FractureMode::Triangles,
+            FractureMode::Spiderweb(Spiderweb::default()),
+        ];
+
+        for mode in modes {
+            let glass = Glass::new_with_density(3.0, 2.0, 4.0).with_fracture(mode);
+            for impact in [None, Some(&impact)] {
+                let [first, second] = shatter_twice(&glass, impact);
+                assert!(!first.is_empty(), "{mode:?} created no cells");
+                assert_eq!(first.len(), second.len(), "{mode:?}");
+                for (a, b) in first.iter().zip(&second) {
+                    // bit-identical, not just close
+                    assert_eq!(
+                        a.pos.to_array().map(f32::to_bits),
+                        b.pos.to_array().map(f32::to_bits)
+                    );
+                    assert_eq!(a.polygons, b.polygons, "{mode:?}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use core::fmt::Debug;
+
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::*;
+
+/// Information passed to a [`SeedGenerator`] when a glass is shattered
+#[derive(Clone, Debug)]
+pub struct SeedContext<'a> {
+    /// Width and height of the glass
+    pub size: Vec2,
+    /// The number of cell points the glass wants along the width and the height, see [`Glass::num_cell_points`].
+    /// Generators are free to interpret this as just a hint for how many points to create
+    pub num_cell_points: UVec2,
+    /// Where the glass was hit, if a [`ShatterImpact`] was inserted along with [`Shattered`]
+    pub impact: Option<&'a ShatterImpact>,
+    /// Points closer than this to each other are discarded, see [`SeedSet`]
+    pub min_distance: f32,
+}
+
+impl SeedContext<'_> {
+    /// Total number of points the glass wants
+    pub fn num_points(&self) -> u32 {
+        self.num_cell_points.x * self.num_cell_points.y
+    }
+}
+
+/// Decides where the cell points (the seeds of the voronoi diagram) of a glass are placed when it is shattered.
+/// Each cell point ends up as the center of a shard.
+///
+/// Set it per glass with [`Glass::with_seeds`]. Implement it yourself for custom patterns.
+pub trait SeedGenerator: Send + Sync + Debug + 'static {
+    /// Returns the cell points, relative to the bottom left of the glass.
+    /// Points outside of the glass or too close to each other are discarded afterwards, so there is no need to check for that.
+    ///
+    /// Use `rng` for all the randomness, so that glass with a seeded [`ShatterRng`] always shatters the same way
+    fn generate(&self, context: &SeedContext, rng: &mut ShatterRng) -> Vec<Vec2>;
+}
+
+/// Divides the glass into a grid and places a random point inside each cell of the grid.
+/// This makes all the shards roughly the same size
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GridJitter;
+
+impl SeedGenerator for GridJitter {
+    fn generate(&self, context: &SeedContext, rng: &mut ShatterRng) -> Vec<Vec2> {
+        let num_cell_points = context.num_cell_points;
+
+        // the full cell width, used to determine the center of each cell
+        let cell_width: f32 = context.size.x / num_cell_points.x as f32;
+        let cell_height: f32 = context.size.y / num_cell_points.y as f32;
+
+        // the max offset a point can be in from the center of the cell
+        let cell_offset = Vec2::new(
+            (cell_width / 2.0) - context.min_distance,
+            (cell_height / 2.0) - context.min_distance,
+        );
+
+        // 2*cell_offset, representing the max offset from one edge of the cell to the other, instead of just to the center
+        let full_cell_offset = cell_offset * 2.0;
+
+        let mut cells: Vec<Vec2> = Vec::with_capacity(context.num_points() as usize);
+
+        // build the cells from bottom left to top right
+        for y in 0..num_cell_points.y {
+            for x in 0..num_cell_points.x {
+                let cell_center = Vec2::new(
+                    (x as f32 + 0.5) * cell_width,
+                    (y as f32 + 0.5) * cell_height,
+                );
+
+                let bottom_left = cell_center - cell_offset;
+
+                // generate a random float inside the cell, using cell_offset_2
+                // then offset it by the bottom left position
+                let rand = Vec2::new(rng.f32(), rng.f32());
+
+                cells.push((rand * full_cell_offset) + bottom_left);
+            }
+        }
+
+        cells
+    }
+}
+
+// how many times a single point can be rejected before giving up on it
+// giving up just means there will be one less shard, which is fine
+pub(crate) const MAX_SEED_ATTEMPTS: u32 = 32;
+
+/// Packs the points around the [`ShatterImpact`], using its radius and falloff.
+/// Points are sampled uniformly over the glass and kept with a probability that drops with the distance to the impact,
+/// so the shards are small near the impact and large far away from it. The number of points is the same as [`GridJitter`].
+///
+/// If the glass was shattered without an impact, this behaves exactly like [`GridJitter`]. This is the default for every [`Glass`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Radial;
+
+impl SeedGenerator for Radial {
+    fn generate(&self, context: &SeedContext, rng: &mut ShatterRng) -> Vec<Vec2> {
+        let Some(impact) = context.impact else {
+            return GridJitter.generate(context, rng);
+        };
+
+        let size = context.size;
+        let radius = impact.radius.max(context.min_distance);
+        let mut seeds = SeedSet::new(size, context.min_distance);
+
+        for _ in 0..context.num_points() {
+            for _ in 0..MAX_SEED_ATTEMPTS {
+                let position = Vec2::new(rng.f32(), rng.f32()) * size;
+
+                let distance = position.distance(impact.point);
+                let density = if distance <= radius {
+                    1.0
+                } else {
+                    ops::powf(radius / distance, impact.falloff)
+                };
+
+                if rng.f32() < density && seeds.insert(position) {
+                    break;
+                }
+            }
+        }
+
+        seeds.into_points()
+    }
+}
+
+/// Poisson-disk sampling (Bridson's algorithm): points are placed randomly, but never closer than `radius` to each other.
+/// This gives shards of similar size that look less regular than [`GridJitter`]
+#[derive(Clone, Copy, Debug)]
+pub struct PoissonDisk {
+    /// Minimum distance between points.
+    /// If `None`, it is computed so that roughly [`SeedContext::num_points`] points fit in the glass
+    pub radius: Option<f32>,
+    /// How many candidates are tried around each point before it is considered done
+    pub attempts: u32,
+}
+
+impl Default for PoissonDisk {
+    fn default() -> Self {
+        Self {
+            radius: None,
+            attempts: 30,
+        }
+    }
+}
+
+impl PoissonDisk {
+    /// Creates a Poisson-disk generator with a fixed minimum distance between points
+    pub fn new(radius: f32) -> Self {
+        Self {
+            radius: Some(radius),
+            ..default()
+        }
+    }
+}
+
+impl SeedGenerator for PoissonDisk {
+    fn generate(&self, context: &SeedContext, rng: &mut ShatterRng) -> Vec<Vec2> {
+        let size = context.size;
+
+        // a disk of radius r "occupies" about 1.5 * r^2 of area when tightly packed like this
+        let radius = self
+            .radius
+            .unwrap_or_else(|| {
+                (size.x * size.y / (1.5 * context.num_points().max(1) as f32)).sqrt()
+            })
+            .max(context.min_distance);
+
+        // background grid where each cell can hold at most one point, used to find neighbours quickly
+        let cell_size = radius / core::f32::consts::SQRT_2;
+        let grid_size = (size / cell_size).ceil().as_uvec2().max(UVec2::ONE);
+        let mut grid: Vec<Option<usize>> = vec![None; (grid_size.x * grid_size.y) as usize];
+        let grid_index = |point: Vec2| {
+            let cell = (point / cell_size).as_uvec2().min(grid_size - UVec2::ONE);
+            (cell.y * grid_size.x + cell.x) as usize
+        };
+
+        let mut points: Vec<Vec2> = Vec::new();
+        let mut active: Vec<usize> = Vec::new();
+
+        let first = Vec2::new(rng.f32(), rng.f32()) * size;
+        grid[grid_index(first)] = Some(0);
+        points.push(first);
+        active.push(0);
+
+        while !active.is_empty() {
+            let active_index = rng.usize(..active.len());
+            let center = points[active[active_index]];
+            let mut found = false;
+
+            for _ in 0..self.attempts {
+                // random point in the annulus between radius and 2 * radius
+                let angle = rng.f32() * core::f32::consts::TAU;
+                let distance = radius * (1.0 + rng.f32());
+                let (sin, cos) = ops::sin_cos(angle);
+                let candidate = center + Vec2::new(cos, sin) * distance;
+
+                if candidate.cmplt(Vec2::ZERO).any() || candidate.cmpge(size).any() {
+                    continue;
+                }
+
+                // points closer than radius can be at most 2 grid cells away
+                let cell = (candidate / cell_size).as_ivec2();
+                let too_close = (-2..=2).any(|y| {
+                    (-2..=2).any(|x| {
+                        let neighbour = cell + IVec2::new(x, y);
+                        if neighbour.cmplt(IVec2::ZERO).any()
+                            || neighbour.cmpge(grid_size.as_ivec2()).any()
+                        {
+                            return false;
+                        }
+                        let index =
+                            (neighbour.y as u32 * grid_size.x + neighbour.x as u32) as usize;
+                        grid[index].is_some_and(|other| points[other].distance(candidate) < radius)
+                    })
+                });
+
+                if !too_close {
+                    grid[grid_index(candidate)] = Some(points.len());
+                    active.push(points.len());
+                    points.push(candidate);
+                    found = true;
+                    break;
+                }
+            }
+
+            if !found {
+                active.swap_remove(active_index);
+            }
+        }
+
+        points
+    }
+}
+
+/// A fixed list of points, relative to the bottom left of the glass.
+/// Useful for hand authored patterns, or points computed somewhere else
+#[derive(Clone, Debug, Default)]
+pub struct PointList(pub Vec<Vec2>);
+
+impl SeedGenerator for PointList {
+    fn generate(&self, _context: &SeedContext, _rng: &mut ShatterRng) -> Vec<Vec2> {
+        self.0.clone()
+    }
+}
+
+/// A set of points that rejects points outside of the glass or too close to the points already in it.
+///
+/// voronator crashes when the cells overlap or are too close, so every [`SeedGenerator`] output goes through this.
+/// You can also use it in your own generators.
+#[derive(Clone, Debug)]
+pub struct SeedSet {
+    size: Vec2,
+    min_distance: f32,
+    // to avoid checking every point against every other point, they are bucketed into a grid of min_distance sized cells,
+    // so only the neighbouring buckets need to be checked
+    buckets: HashMap<IVec2, Vec<Vec2>>,
+    points: Vec<Vec2>,
+}
+
+impl SeedSet {
+    /// Creates an empty set for a glass of the given width and height
+    pub fn new(size: Vec2, min_distance: f32) -> Self {
+        Self {
+            size,
+            min_distance,
+            buckets: HashMap::new(),
+            points: Vec::new(),
+        }
+    }
+
+    /// Adds a point, returning false if it was rejected
+    pub fn insert(&mut self, point: Vec2) -> bool {
+        if !point.is_finite() || point.cmplt(Vec2::ZERO).any() || point.cmpgt(self.size).any() {
+            return false;
+        }
+
+        let bucket = (point / self.min_distance).floor().as_ivec2();
+        let too_close = (-1..=1).any(|y| {
+            (-1..=1).any(|x| {
+                self.buckets
+                    .get(&(bucket + IVec2::new(x, y)))
+                    .is_some_and(|others| {
+                        others
+                            .iter()
+                            .any(|other| other.distance(point) < self.min_distance)
+                    })
+            })
+        });
+
+        if too_close {
+            return false;
+        }
+
+        self.buckets.entry(bucket).or_default().push(point);
+        self.points.push(point);
+        true
+    }
+
+    /// The points that were accepted, in insertion order
+    pub fn points(&self) -> &[Vec2] {
+        &self.points
+    }
+
+    /// Consumes the set, returning the points that were accepted
+    pub fn into_points(self) -> Vec<Vec2> {
+        self.points
+    }
+}
@@ -0,0 +1,141 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{geometry::*, *};
+
+/// The outline of a [`Glass`], for round portholes, arched windows, etc.
+///
+/// The outline is stretched to fill the width and height of the glass, so only its proportions matter:
+/// a [`Circle`] in a glass that is wider than it is tall becomes an ellipse.
+/// The cells are clipped to the outline when the glass is shattered, so no shards are created outside of it.
+///
+/// The glass itself needs a mesh and collider that match, [`AutoGlass`] adds them automatically.
+/// Otherwise, use [`Glass::mesh`] and [`Glass::collider`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum GlassShape {
+    /// The whole width and height of the glass
+    #[default]
+    Rectangle,
+    /// A circle, or an ellipse if the glass is not square
+    Circle(Circle),
+    /// An ellipse
+    Ellipse(Ellipse),
+    /// A polygon with all sides the same length, such as a hexagon
+    RegularPolygon(RegularPolygon),
+    /// Any polygon without self intersections, such as an arch or a trapezoid.
+    /// It can be concave and the points can be in any order (clockwise or counter-clockwise)
+    Polygon(Vec<Vec2>),
+}
+
+impl GlassShape {
+    /// Number of points used for the outline of circles and ellipses
+    pub const ROUND_SEGMENTS: u32 = 32;
+
+    /// Points of the outline, counter-clockwise and relative to the bottom left of a glass of the given width and height
+    pub fn outline(&self, size: Vec2) -> Vec<Vec2> {
+        let points: Vec<Vec2> = match self {
+            GlassShape::Rectangle => return rectangle(size).to_vec(),
+            GlassShape::Circle(circle) => ellipse_points(Vec2::splat(circle.radius)),
+            GlassShape::Ellipse(ellipse) => ellipse_points(ellipse.half_size),
+            GlassShape::RegularPolygon(polygon) => polygon.vertices(0.0).into_iter().collect(),
+            GlassShape::Polygon(points) => points.clone(),
+        };
+
+        // stretch the bounding box of the points to fill the glass
+        let min = points.iter().copied().fold(Vec2::MAX, Vec2::min);
+        let max = points.iter().copied().fold(Vec2::MIN, Vec2::max);
+        let extent = (max - min).max(Vec2::splat(f32::EPSILON));
+
+        counter_clockwise(
+            points
+                .into_iter()
+                .map(|point| (point - min) / extent * size)
+                .collect(),
+        )
+    }
+}
+
+/// A hole in a [`Glass`], such as a mail slot or a hole for a pipe. Add it with [`Glass::with_hole`].
+/// The shards are clipped around it, and the mesh and collider from [`Glass::mesh`] and [`Glass::collider`] have the hole in them.
+///
+/// Holes must be inside the outline of the glass and must not overlap each other
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlassHole {
+    /// Outline of the hole, stretched to fill `rect`
+    pub shape: GlassShape,
+    /// Where the hole is, as a fraction of the width and height of the glass:
+    /// (0, 0) is the bottom left of the glass and (1, 1) the top right
+    pub rect: Rect,
+}
+
+impl GlassHole {
+    /// Creates a hole with the given outline, filling `rect`. See [`GlassHole::rect`]
+    pub fn new(shape: GlassShape, rect: Rect) -> Self {
+        Self { shape, rect }
+    }
+
+    /// Points of the hole, counter-clockwise and relative to the bottom left of a glass of the given width and height
+    pub fn outline(&self, size: Vec2) -> Vec<Vec2> {
+        let offset = self.rect.min * size;
+        self.shape
+            .outline(self.rect.size() * size)
+            .into_iter()
+            .map(|point| point + offset)
+            .collect()
+    }
+}
+
+fn ellipse_points(half_size: Vec2) -> Vec<Vec2> {
+    (0..GlassShape::ROUND_SEGMENTS)
+        .map(|i| {
+            let angle = i as f32 * core::f32::consts::TAU / GlassShape::ROUND_SEGMENTS as f32;
+            let (sin, cos) = ops::sin_cos(angle);
+            Vec2::new(cos, sin) * half_size
+        })
+        .collect()
+}
+
+/// Extrudes convex polygons relative to the bottom left of a glass of the given size into a mesh for the glass itself,
+/// centered on the glass entity
+pub(crate) fn outline_mesh(
+    polygons: &[Vec<Vec2>],
+    size: Vec3,
+    curvature: Option<&GlassCurvature>,
+) -> Option<Mesh> {
+    extrude_glass(polygons, size, curvature).map(|mesh| mesh.transformed_by(bottom_left(size)))
+}
+
+/// Like [`outline_mesh`], but for the collider of the glass. Each polygon becomes a convex hull
+pub(crate) fn outline_collider(
+    polygons: &[Vec<Vec2>],
+    size: Vec3,
+    curvature: Option<&GlassCurvature>,
+) -> Option<Collider> {
+    let transform = bottom_left(size);
+
+    // curved glass is split into small pieces, so that each hull follows the curve
+    let polygons = match curvature {
+        Some(curvature) => curvature.subdivide(polygons),
+        None => polygons.to_vec(),
+    };
+
+    let parts: Vec<(Position, Rotation, Collider)> = polygons
+        .iter()
+        .filter_map(|polygon| {
+            let points: Vec<Vec3> = polygon
+                .iter()
+                .flat_map(|point| [point.extend(0.0), point.extend(-size.z)])
+                .map(|point| match curvature {
+                    Some(curvature) => curvature.bend(point, size.xy()),
+                    None => point,
+                })
+                .map(|point| transform.transform_point(point))
+                .collect();
+
+            Collider::convex_hull(points)
+        })
+        .map(|collider| (Position::default(), Rotation::default(), collider))
+        .collect();
+
+    (!parts.is_empty()).then(|| Collider::compound(parts))
+}
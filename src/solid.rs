@@ -0,0 +1,612 @@
+use avian3d::prelude::*;
+use bevy::{
+    asset::RenderAssetUsages,
+    platform::collections::HashSet,
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+};
+
+use crate::*;
+
+/// Marks an entity with a convex [`Mesh3d`] as a solid piece of glass, such as a glass block, an ice cube or a crystal pillar.
+/// Add [`Shattered`] to it to split its whole volume into convex shards, using a 3D voronoi diagram.
+/// Each shard gets a [`Shard`], a [`SolidShard`] and a convex hull collider, and can be [`Shattered`] again.
+/// A [`ShatterImpact`] with some energy pushes the shards away from its point, like it does for glass.
+///
+/// The mesh must be available on the CPU, which is the default for meshes made from bevy's primitives.
+/// It must also be convex, since the shards are cut out of the planes of its triangles: concave meshes lose the parts that stick out.
+/// The shards use the same transform as the solid, placed in the world if it has a parent
+#[derive(Component, Clone, Debug)]
+pub struct SolidGlass {
+    /// Number of cell points, each one becomes a shard
+    pub num_cells: u32,
+    /// Physics material given to each shard. Defaults to `None`, using avian's defaults
+    pub physics: Option<ShardPhysics>,
+}
+
+impl SolidGlass {
+    /// Creates a solid that breaks into about `num_cells` shards
+    pub fn new(num_cells: u32) -> Self {
+        Self {
+            num_cells,
+            physics: None,
+        }
+    }
+
+    /// Changes the physics material given to each shard
+    pub fn with_physics(mut self, physics: ShardPhysics) -> Self {
+        self.physics = Some(physics);
+        self
+    }
+}
+
+/// Added to every shard of a [`SolidGlass`], along with [`Shard`]. These shards have no [`Shard::polygons`]
+#[derive(Component, Clone, Debug)]
+pub struct SolidShard {
+    /// The cell point the shard was generated from, in the same space as the vertices of the mesh of the solid
+    pub seed: Vec3,
+    /// Faces of the shard, in the same space as the vertices of the mesh of the solid (see [`Shard::centroid`]).
+    /// Each one is a convex polygon that is counter-clockwise when seen from outside
+    pub faces: Vec<Vec<Vec3>>,
+}
+
+/// A solid or one of its shards. Solids have a mesh to split, shards have their faces
+type ShatterSolid<'a> = (
+    &'a Transform,
+    &'a GlobalTransform,
+    Has<ChildOf>,
+    &'a MeshMaterial3d<StandardMaterial>,
+    Option<&'a Mesh3d>,
+    Option<&'a SolidShard>,
+    Option<&'a Shard>,
+    Option<&'a ShatterImpact>,
+    Option<&'a mut ShatterRng>,
+);
+
+/// Solids and their shards
+type SolidOrSolidShard = Or<(With<SolidGlass>, With<SolidShard>)>;
+
+/// Hook to split a [`SolidGlass`] (or one of its shards) into shards when [`Shattered`] is added to it
+pub(super) fn solid_shatter_hook(
+    trigger: Trigger<OnAdd, Shattered>,
+    mut solids: Populated<ShatterSolid, SolidOrSolidShard>,
+    settings: Query<&SolidGlass>,
+    parents: Query<&ShardOf>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    velocities: Velocities,
+) {
+    let entity = trigger.target();
+
+    let Ok((
+        transform,
+        global_transform,
+        has_parent,
+        material,
+        mesh,
+        solid_shard,
+        shard,
+        impact,
+        rng,
+    )) = solids.get_mut(entity)
+    else {
+        return;
+    };
+
+    // the shards are placed in the world, even if the solid is the child of something
+    let transform = if has_parent {
+        global_transform.compute_transform()
+    } else {
+        *transform
+    };
+    // the faces of a shard are relative to the solid, not to the center of the shard
+    let transform =
+        transform * Transform::from_translation(-shard.map_or(Vec3::ZERO, |shard| shard.centroid));
+
+    // the settings come from the solid this shard (or the shard it came from, and so on) was created from
+    let mut root = entity;
+    let solid = loop {
+        if let Ok(solid) = settings.get(root) {
+            break solid;
+        }
+        let Ok(parent) = parents.get(root) else {
+            warn!("Could not find the solid glass of a shard, it will not be shattered");
+            return;
+        };
+        root = parent.0;
+    };
+
+    let faces = match solid_shard {
+        Some(solid_shard) => solid_shard.faces.clone(),
+        None => {
+            let Some(faces) = mesh
+                .and_then(|mesh| meshes.get(&mesh.0))
+                .and_then(mesh_faces)
+            else {
+                warn!(
+                    "Could not read the mesh of a solid glass, it needs a triangle list mesh that is available on the CPU"
+                );
+                return;
+            };
+            faces
+        }
+    };
+
+    let mut rng = ShatterRng::or_random(rng);
+
+    let generation = shard.map_or(0, |shard| shard.generation + 1);
+
+    let cells = voronoi_cells(&faces, solid.num_cells, &mut rng);
+
+    // every shard needs the entities of its neighbors, so they all get one before any is spawned
+    let ids: Vec<Entity> = cells.iter().map(|_| commands.spawn_empty().id()).collect();
+    let neighbors = cell_neighbors(&cells);
+    let shards: Vec<(Vec3, Vec<Vec<Vec3>>, FracturedShard)> = cells
+        .into_iter()
+        .zip(neighbors)
+        .map(|((seed, faces), neighbors)| {
+            // each shard is placed at its own center, so that it rotates around it
+            let (volume, centroid) = faces_volume(&faces);
+            let points: Vec<Vec3> = faces
+                .iter()
+                .flatten()
+                .map(|&point| point - centroid)
+                .collect();
+
+            let area: f32 = faces
+                .iter()
+                .map(|face| newell_normal(face).length() / 2.0)
+                .sum();
+            let radius = points
+                .iter()
+                .map(|point| point.length())
+                .fold(0.0, f32::max);
+            let min_angle = faces
+                .iter()
+                .map(|face| face_min_angle(face))
+                .fold(core::f32::consts::PI, f32::min);
+
+            let shard = FracturedShard {
+                pos: seed.xy(),
+                centroid,
+                outline: Vec::new(),
+                area,
+                volume,
+                radius,
+                min_angle,
+                neighbors: neighbors.into_iter().map(|i| ids[i]).collect(),
+                on_frame: false,
+                polygons: Vec::new(),
+                generation,
+                mesh: faces_mesh(&faces).translated_by(-centroid),
+                collider: Collider::convex_hull(points),
+            };
+            (seed, faces, shard)
+        })
+        .collect();
+
+    let to_world = GlobalTransform::from(transform);
+    let mut push = impact.and_then(|impact| impact.push(&to_world));
+    if let Some(push) = &mut push {
+        push.share(
+            shards
+                .iter()
+                .map(|(_, _, shard)| to_world.transform_point(shard.centroid)),
+        );
+    }
+    let placement = ShardPlacement {
+        transform,
+        parent: None,
+        to_world,
+        velocity: velocities.get(entity),
+        push,
+    };
+
+    for ((seed, faces, shard), &id) in shards.into_iter().zip(&ids) {
+        let mut shard_entity = commands.entity(id);
+        shard_entity.insert(SolidShard { seed, faces });
+        spawn_shard(
+            &mut shard_entity,
+            &mut meshes,
+            entity,
+            placement,
+            material,
+            solid.physics.as_ref(),
+            shard,
+        );
+    }
+}
+
+// points closer than this are merged, and cells smaller than this are dropped
+const EPSILON: f32 = 0.001;
+
+/// Splits a convex polyhedron into voronoi cells, returning the cell point and the faces of each one.
+/// Each cell is the polyhedron cut by the planes halfway between its point and every other point
+fn voronoi_cells(
+    faces: &[Vec<Vec3>],
+    num_cells: u32,
+    rng: &mut ShatterRng,
+) -> Vec<(Vec3, Vec<Vec<Vec3>>)> {
+    let min = faces.iter().flatten().copied().fold(Vec3::MAX, Vec3::min);
+    let max = faces.iter().flatten().copied().fold(Vec3::MIN, Vec3::max);
+    let planes: Vec<(Vec3, f32)> = faces.iter().filter_map(|face| face_plane(face)).collect();
+
+    // random points inside of the polyhedron
+    let mut seeds: Vec<Vec3> = Vec::with_capacity(num_cells as usize);
+    for _ in 0..num_cells {
+        for _ in 0..MAX_SEED_ATTEMPTS {
+            let point = min + Vec3::new(rng.f32(), rng.f32(), rng.f32()) * (max - min);
+
+            let inside = planes
+                .iter()
+                .all(|&(normal, distance)| normal.dot(point) < distance - EPSILON);
+            let too_close = seeds.iter().any(|seed| seed.distance(point) < EPSILON);
+
+            if inside && !too_close {
+                seeds.push(point);
+                break;
+            }
+        }
+    }
+
+    seeds
+        .iter()
+        .filter_map(|&seed| {
+            let mut cell = faces.to_vec();
+            for &other in seeds.iter() {
+                if other == seed {
+                    continue;
+                }
+                let normal = (other - seed).normalize();
+                let distance = normal.dot((seed + other) / 2.0);
+                cell = clip_faces(&cell, normal, distance);
+            }
+
+            // a closed polyhedron has at least 4 faces
+            (cell.len() >= 4).then_some((seed, cell))
+        })
+        .collect()
+}
+
+/// Every triangle of a triangle list mesh, `None` if the mesh has another topology or its positions are not available
+pub(crate) fn mesh_triangles(mesh: &Mesh) -> Option<Vec<[Vec3; 3]>> {
+    if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+        return None;
+    }
+
+    let positions: Vec<Vec3> = mesh
+        .attribute(Mesh::ATTRIBUTE_POSITION)?
+        .as_float3()?
+        .iter()
+        .map(|&position| Vec3::from(position))
+        .collect();
+
+    let indices: Vec<usize> = match mesh.indices() {
+        Some(indices) => indices.iter().collect(),
+        None => (0..positions.len()).collect(),
+    };
+
+    Some(
+        indices
+            .chunks_exact(3)
+            .map(|triangle| [triangle[0], triangle[1], triangle[2]].map(|index| positions[index]))
+            .collect(),
+    )
+}
+
+/// Faces of the convex hull of a mesh, found by cutting a box around it with the plane of every triangle
+fn mesh_faces(mesh: &Mesh) -> Option<Vec<Vec<Vec3>>> {
+    let triangles = mesh_triangles(mesh)?;
+    let positions = triangles.iter().flatten();
+
+    let min = positions.clone().copied().fold(Vec3::MAX, Vec3::min);
+    let max = positions.copied().fold(Vec3::MIN, Vec3::max);
+    let mut faces = box_faces(min, max);
+
+    // most meshes have several triangles (and duplicated vertices) on the same plane, each plane only needs to cut once
+    let mut planes: HashSet<(IVec3, i32)> = HashSet::new();
+    for [a, b, c] in triangles {
+        let Some(normal) = (b - a).cross(c - a).try_normalize() else {
+            continue;
+        };
+        let distance = normal.dot(a);
+
+        let key = (
+            (normal / EPSILON).round().as_ivec3(),
+            (distance / EPSILON).round() as i32,
+        );
+        if planes.insert(key) {
+            faces = clip_faces(&faces, normal, distance);
+        }
+    }
+
+    (faces.len() >= 4).then_some(faces)
+}
+
+/// The 6 faces of an axis aligned box
+fn box_faces(min: Vec3, max: Vec3) -> Vec<Vec<Vec3>> {
+    let corner = |x: bool, y: bool, z: bool| {
+        Vec3::new(
+            if x { max.x } else { min.x },
+            if y { max.y } else { min.y },
+            if z { max.z } else { min.z },
+        )
+    };
+
+    // each face goes around one corner of the box, then the orientation is fixed afterwards
+    let faces = [
+        [
+            corner(false, false, false),
+            corner(false, true, false),
+            corner(false, true, true),
+            corner(false, false, true),
+        ],
+        [
+            corner(true, false, false),
+            corner(true, true, false),
+            corner(true, true, true),
+            corner(true, false, true),
+        ],
+        [
+            corner(false, false, false),
+            corner(true, false, false),
+            corner(true, false, true),
+            corner(false, false, true),
+        ],
+        [
+            corner(false, true, false),
+            corner(true, true, false),
+            corner(true, true, true),
+            corner(false, true, true),
+        ],
+        [
+            corner(false, false, false),
+            corner(true, false, false),
+            corner(true, true, false),
+            corner(false, true, false),
+        ],
+        [
+            corner(false, false, true),
+            corner(true, false, true),
+            corner(true, true, true),
+            corner(false, true, true),
+        ],
+    ];
+
+    let center = (min + max) / 2.0;
+    faces
+        .into_iter()
+        .map(|face| {
+            let mut face = face.to_vec();
+            if newell_normal(&face).dot(face[0] - center) < 0.0 {
+                face.reverse();
+            }
+            face
+        })
+        .collect()
+}
+
+/// Cuts a convex polyhedron with a plane, keeping the part behind it (where `normal.dot(point) <= distance`).
+/// The hole left by the cut is closed with a new face
+fn clip_faces(faces: &[Vec<Vec3>], normal: Vec3, distance: f32) -> Vec<Vec<Vec3>> {
+    let mut clipped_faces: Vec<Vec<Vec3>> = Vec::with_capacity(faces.len() + 1);
+    let mut cut_points: Vec<Vec3> = Vec::new();
+
+    for face in faces {
+        let mut clipped: Vec<Vec3> = Vec::with_capacity(face.len() + 1);
+        for (i, &a) in face.iter().enumerate() {
+            let b = face[(i + 1) % face.len()];
+            let a_side = normal.dot(a) - distance;
+            let b_side = normal.dot(b) - distance;
+
+            if a_side <= 0.0 {
+                clipped.push(a);
+            }
+            if (a_side <= 0.0) != (b_side <= 0.0) {
+                let point = a.lerp(b, a_side / (a_side - b_side));
+                clipped.push(point);
+                cut_points.push(point);
+            }
+        }
+
+        dedup_points(&mut clipped);
+        if clipped.len() >= 3 {
+            clipped_faces.push(clipped);
+        }
+    }
+
+    // the new face is made of every point where an edge was cut, sorted around their center
+    // so that it is counter-clockwise when seen from outside (from the side the normal points to)
+    if cut_points.len() >= 3 {
+        let center = cut_points.iter().sum::<Vec3>() / cut_points.len() as f32;
+        let u = normal.any_orthonormal_vector();
+        let v = normal.cross(u);
+        cut_points.sort_by(|a, b| {
+            let angle =
+                |point: &Vec3| ops::atan2((*point - center).dot(v), (*point - center).dot(u));
+            angle(a).total_cmp(&angle(b))
+        });
+
+        dedup_points(&mut cut_points);
+        if cut_points.len() >= 3 {
+            clipped_faces.push(cut_points);
+        }
+    }
+
+    clipped_faces
+}
+
+/// Removes points that are too close to the previous one
+fn dedup_points(points: &mut Vec<Vec3>) {
+    points.dedup_by(|a, b| a.distance(*b) < EPSILON);
+    if points.len() > 1 && points[0].distance(points[points.len() - 1]) < EPSILON {
+        points.pop();
+    }
+}
+
+/// Normal of a polygon, not normalized. Works for any polygon, even if some points are collinear
+fn newell_normal(face: &[Vec3]) -> Vec3 {
+    let mut normal = Vec3::ZERO;
+    for (i, &a) in face.iter().enumerate() {
+        let b = face[(i + 1) % face.len()];
+        normal += (a - b).cross(a + b);
+    }
+    normal
+}
+
+/// Plane of a face, as a normal pointing outside and the distance to the origin along it
+fn face_plane(face: &[Vec3]) -> Option<(Vec3, f32)> {
+    let normal = newell_normal(face).try_normalize()?;
+    Some((normal, normal.dot(face[0])))
+}
+
+/// Volume and center of mass of a convex polyhedron, from the tetrahedra between each of its triangles and the average of its points
+fn faces_volume(faces: &[Vec<Vec3>]) -> (f32, Vec3) {
+    let points = faces.iter().flatten();
+    let reference = points.clone().sum::<Vec3>() / points.count().max(1) as f32;
+
+    let mut volume = 0.0;
+    let mut centroid = Vec3::ZERO;
+    for face in faces {
+        for i in 1..face.len().saturating_sub(1) {
+            let (a, b, c) = (face[0], face[i], face[i + 1]);
+            let tetrahedron = (a - reference).dot((b - reference).cross(c - reference)) / 6.0;
+            volume += tetrahedron;
+            centroid += (reference + a + b + c) / 4.0 * tetrahedron;
+        }
+    }
+
+    if volume.abs() <= f32::EPSILON {
+        return (0.0, reference);
+    }
+    (volume, centroid / volume)
+}
+
+/// Smallest angle between two edges of a face, in radians
+fn face_min_angle(face: &[Vec3]) -> f32 {
+    let n = face.len();
+    (0..n)
+        .filter_map(|i| {
+            let to_previous = face[(i + n - 1) % n] - face[i];
+            let to_next = face[(i + 1) % n] - face[i];
+            // faces are convex, so the angle is never reflex
+            Some(to_previous.try_normalize()?.dot(to_next.try_normalize()?))
+        })
+        .map(|cos| ops::acos(cos.clamp(-1.0, 1.0)))
+        .fold(core::f32::consts::PI, f32::min)
+}
+
+/// For each voronoi cell, the indices of the cells it shares a face with
+fn cell_neighbors(cells: &[(Vec3, Vec<Vec<Vec3>>)]) -> Vec<Vec<usize>> {
+    let mut neighbors: Vec<Vec<usize>> = vec![Vec::new(); cells.len()];
+    for (i, (seed, faces)) in cells.iter().enumerate() {
+        for (j, (other, _)) in cells.iter().enumerate().skip(i + 1) {
+            // cells that touch have a face on the plane halfway between their cell points
+            let normal = (*other - *seed).normalize();
+            let distance = normal.dot((*seed + *other) / 2.0);
+            let touches = faces.iter().filter_map(|face| face_plane(face)).any(
+                |(face_normal, face_distance)| {
+                    face_normal.dot(normal) > 1.0 - EPSILON
+                        && (face_distance - distance).abs() < EPSILON
+                },
+            );
+
+            if touches {
+                neighbors[i].push(j);
+                neighbors[j].push(i);
+            }
+        }
+    }
+    neighbors
+}
+
+/// Mesh of a convex polyhedron, with flat normals
+fn faces_mesh(faces: &[Vec<Vec3>]) -> Mesh {
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+
+    for face in faces {
+        let normal = newell_normal(face).normalize_or_zero();
+        let start = positions.len() as u32;
+
+        // every face is convex, so a fan is enough
+        for i in 1..face.len().saturating_sub(1) as u32 {
+            indices.extend_from_slice(&[start, start + i, start + i + 1]);
+        }
+
+        positions.extend_from_slice(face);
+        normals.extend(core::iter::repeat_n(normal, face.len()));
+    }
+
+    // I assume I will never need the mesh on the CPU again
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cells of a 1x2x0.5 box and of an ico sphere, with their total volume
+    fn solids() -> Vec<(Vec<(Vec3, Vec<Vec<Vec3>>)>, f32)> {
+        let mut rng = ShatterRng::with_seed(3);
+        let sphere = mesh_faces(&Sphere::new(1.0).mesh().ico(2).unwrap()).unwrap();
+        let (sphere_volume, _) = faces_volume(&sphere);
+        vec![
+            (
+                voronoi_cells(
+                    &box_faces(Vec3::ZERO, Vec3::new(1.0, 2.0, 0.5)),
+                    20,
+                    &mut rng,
+                ),
+                1.0,
+            ),
+            (voronoi_cells(&sphere, 30, &mut rng), sphere_volume),
+        ]
+    }
+
+    #[test]
+    fn cells_keep_the_volume() {
+        for (cells, volume) in solids() {
+            assert!(cells.len() > 1);
+            let total: f32 = cells.iter().map(|(_, faces)| faces_volume(faces).0).sum();
+            assert!((total - volume).abs() < 1e-3, "{total} instead of {volume}");
+        }
+    }
+
+    #[test]
+    fn faces_point_outwards() {
+        for (cells, _) in solids() {
+            for (_, faces) in cells {
+                let (_, centroid) = faces_volume(&faces);
+                for face in faces {
+                    let center = face.iter().sum::<Vec3>() / face.len() as f32;
+                    assert!(newell_normal(&face).dot(center - centroid) > 0.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn neighbors_are_symmetric() {
+        for (cells, _) in solids() {
+            let neighbors = cell_neighbors(&cells);
+            assert!(neighbors.iter().any(|cell| !cell.is_empty()));
+            for (i, cell) in neighbors.iter().enumerate() {
+                for &j in cell {
+                    assert_ne!(i, j);
+                    assert!(
+                        neighbors[j].contains(&i),
+                        "{i} touches {j} but not the other way"
+                    );
+                }
+            }
+        }
+    }
+}
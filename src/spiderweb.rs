@@ -0,0 +1,214 @@
+use bevy::prelude::*;
+
+use crate::{geometry::*, *};
+
+/// Settings for [`FractureMode::Spiderweb`], the way annealed glass breaks when hit by a bullet or a thrown object.
+///
+/// Cracks go out from the impact point like the spokes of a wheel, and are joined by concentric rings that get further apart the further they are from the impact.
+/// This leaves long and thin slivers near the impact, and larger pieces far from it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spiderweb {
+    /// Number of cracks that go out from the impact, must be at least 3
+    pub spokes: u32,
+    /// Distance from the impact to the first ring
+    pub first_ring: f32,
+    /// How much the distance between rings grows. With 1.0 all rings are evenly spaced,
+    /// with 2.0 the distance between two rings is twice the distance between the previous two
+    pub ring_growth: f32,
+    /// How much each spoke is randomly rotated, as a fraction of the angle between two spokes (0.0 to 1.0).
+    /// With only 3 spokes, it is limited to 0.45 so that the cells stay convex
+    pub angular_jitter: f32,
+    /// How much the rings are randomly moved along each spoke, as a fraction of the distance between rings (0.0 to 1.0).
+    /// This makes the rings look less like perfect polygons
+    pub ring_jitter: f32,
+}
+
+impl Default for Spiderweb {
+    fn default() -> Self {
+        Self {
+            spokes: 12,
+            first_ring: 0.15,
+            ring_growth: 1.5,
+            angular_jitter: 0.4,
+            ring_jitter: 0.3,
+        }
+    }
+}
+
+impl Spiderweb {
+    /// Distance from the impact to each ring, growing by `ring_growth`, until `max_radius` is reached
+    fn ring_radii(&self, max_radius: f32) -> Vec<f32> {
+        // with a ring_growth below 1.0 the rings could never reach max_radius
+        const MAX_RINGS: usize = 64;
+
+        let mut radii = Vec::new();
+        let mut spacing = self.first_ring.max(max_radius / MAX_RINGS as f32);
+        let mut radius = spacing;
+
+        while radius < max_radius && radii.len() < MAX_RINGS - 1 {
+            radii.push(radius);
+            spacing *= self.ring_growth;
+            radius += spacing;
+        }
+        radii.push(max_radius);
+
+        radii
+    }
+
+    /// Splits a glass of the given width and height into cells, with the web centered at `center`
+    pub(crate) fn cells(
+        &self,
+        center: Vec2,
+        size: Vec2,
+        rng: &mut ShatterRng,
+        epsilon: f32,
+    ) -> Vec<Cell> {
+        let radii = self.ring_radii(max_radius(center, size, epsilon));
+        self.cells_with_radii(center, &radii, size, rng, epsilon)
+    }
+
+    /// Builds the cells between every pair of spokes and rings, clipped to the glass.
+    /// `radii` are used instead of the rings of the web, they must be sorted and the last one must reach every corner of the glass, see [`max_radius`]
+    pub(crate) fn cells_with_radii(
+        &self,
+        center: Vec2,
+        radii: &[f32],
+        size: Vec2,
+        rng: &mut ShatterRng,
+        epsilon: f32,
+    ) -> Vec<Cell> {
+        let spokes = self.spokes.max(3) as usize;
+
+        // two neighboring spokes are up to (1.0 + angular_jitter) * spoke_angle apart, which has to stay under 180 degrees
+        // for the cells around the center to be convex. With 3 spokes this needs a jitter below 0.5, with more anything below 1.0 works.
+        // Jitter below 1.0 also keeps spokes and rings from crossing each other
+        let max_angular_jitter = (spokes as f32 / 2.0 - 1.0).min(1.0) * 0.9;
+        let angular_jitter = self.angular_jitter.clamp(0.0, max_angular_jitter);
+        let ring_jitter = self.ring_jitter.clamp(0.0, 0.9);
+
+        // rotate the whole pattern randomly, otherwise every glass would crack the same way
+        let start_angle = rng.f32() * core::f32::consts::TAU;
+        let spoke_angle = core::f32::consts::TAU / spokes as f32;
+        let directions: Vec<Vec2> = (0..spokes)
+            .map(|spoke| {
+                let jitter = (rng.f32() - 0.5) * angular_jitter;
+                let angle = start_angle + (spoke as f32 + jitter) * spoke_angle;
+                let (sin, cos) = ops::sin_cos(angle);
+                Vec2::new(cos, sin)
+            })
+            .collect();
+
+        // the last ring is never moved, but its edges cut inside the circle that reaches the corners,
+        // by cos(gap / 2) for two spokes that are gap apart. Pushing it out by the widest gap makes them reach the corners again
+        let widest = (0..spokes)
+            .map(|spoke| directions[spoke].dot(directions[(spoke + 1) % spokes]))
+            .fold(1.0, f32::min);
+        let outer_scale = ((1.0 + widest) / 2.0).sqrt().recip();
+
+        // the distance of each ring along each spoke
+        let spoke_radii: Vec<Vec<f32>> = (0..spokes)
+            .map(|_| {
+                radii
+                    .iter()
+                    .enumerate()
+                    .map(|(ring, &radius)| {
+                        if ring + 1 == radii.len() {
+                            return radius * outer_scale;
+                        }
+                        let previous = if ring == 0 { 0.0 } else { radii[ring - 1] };
+                        let spacing = radii[ring + 1].min(radius * 2.0 - previous) - radius;
+                        radius + (rng.f32() - 0.5) * ring_jitter * spacing
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let glass = rectangle(size);
+        let mut cells = Vec::new();
+
+        for spoke in 0..spokes {
+            let next = (spoke + 1) % spokes;
+            let point =
+                |spoke: usize, ring: usize| center + directions[spoke] * spoke_radii[spoke][ring];
+
+            for ring in 0..radii.len() {
+                let web = if ring == 0 {
+                    vec![center, point(spoke, 0), point(next, 0)]
+                } else {
+                    vec![
+                        point(spoke, ring - 1),
+                        point(spoke, ring),
+                        point(next, ring),
+                        point(next, ring - 1),
+                    ]
+                };
+
+                let polygon = clip_polygon(&web, &glass, epsilon);
+                if polygon.len() < 3 || polygon_area(&polygon) <= epsilon * epsilon {
+                    continue;
+                }
+
+                cells.push(Cell {
+                    pos: polygon_centroid(&polygon),
+                    polygons: vec![polygon],
+                });
+            }
+        }
+
+        cells
+    }
+}
+
+/// The distance from `center` to the furthest corner of the glass.
+/// The last ring has to reach it, otherwise there would be missing pieces
+pub(crate) fn max_radius(center: Vec2, size: Vec2, epsilon: f32) -> f32 {
+    rectangle(size)
+        .iter()
+        .map(|corner| corner.distance(center))
+        .fold(0.0, f32::max)
+        + epsilon
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{geometry::*, *};
+
+    #[test]
+    fn cells_cover_the_glass() {
+        let size = Vec2::new(3.0, 2.0);
+        let modes = [
+            FractureMode::Wedges {
+                spokes: 3,
+                rings: 4,
+            },
+            FractureMode::Spiderweb(Spiderweb::default()),
+            FractureMode::Spiderweb(Spiderweb {
+                spokes: 3,
+                angular_jitter: 1.0,
+                ..default()
+            }),
+        ];
+
+        for mode in modes {
+            for center in [size / 2.0, Vec2::new(0.2, 1.7)] {
+                let impact = ShatterImpact::new(center);
+                let mut rng = ShatterRng::with_seed(7);
+                let cells = mode.cells(&[], size, Some(&impact), &mut rng, 0.001);
+
+                let mut area = 0.0;
+                for polygon in cells.iter().flat_map(|cell| &cell.polygons) {
+                    assert!(
+                        polygon_area(polygon) > 0.0,
+                        "{mode:?} made a clockwise cell"
+                    );
+                    assert!(is_convex(polygon), "{mode:?} made a concave cell");
+                    area += polygon_area(polygon);
+                }
+                assert!(
+                    (area - size.x * size.y).abs() < 1e-3,
+                    "{mode:?} covers {area}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use avian3d::prelude::*;
+use bevy::{platform::collections::HashSet, prelude::*};
+
+use crate::*;
+
+/// Add this to a [`Glass`] to make its shards hold each other up, like a real broken window.
+/// Requires [`ShatterPlugin`] to have been added.
+///
+/// Shards that are not [`RigidBody::Dynamic`] stay in place as long as they are connected to the frame of the glass,
+/// through their [`Shard::neighbors`] and down to a shard that is [`Shard::on_frame`].
+/// Whenever shards are despawned or become dynamic, every cluster that lost its connection to the frame
+/// is marked as [`Unsupported`] and becomes [`RigidBody::Dynamic`], so it falls.
+///
+/// Only the shards of the glass itself are considered, not the shards of its shards.
+/// With [`PartialShatter`], what is left of the glass does not hold the shards up
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct StructuralSupport;
+
+/// Added to the shards of a glass with [`StructuralSupport`] that are no longer connected to its frame, along with [`RigidBody::Dynamic`]
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Unsupported;
+
+/// Finds the shards that are no longer connected to the frame whenever the shards of a glass change
+pub(super) fn update_shard_support(
+    mut commands: Commands,
+    changed_glasses: Query<Entity, (With<StructuralSupport>, Changed<Shards>)>,
+    changed_shards: Query<&ShardOf, Changed<RigidBody>>,
+    glasses: Query<&Shards, With<StructuralSupport>>,
+    shards: Query<(&Shard, Option<&RigidBody>), Without<Unsupported>>,
+) {
+    let dirty: HashSet<Entity> = changed_glasses
+        .iter()
+        .chain(changed_shards.iter().map(|shard_of| shard_of.0))
+        .collect();
+
+    for glass in dirty {
+        let Ok(glass_shards) = glasses.get(glass) else {
+            continue;
+        };
+
+        // dynamic shards are already falling, they don't hold anything up
+        let holding = |entity: Entity| {
+            shards
+                .get(entity)
+                .is_ok_and(|(_, body)| body.is_none_or(|body| !body.is_dynamic()))
+        };
+
+        // walk the neighbors starting from the frame, whatever is not reached has nothing holding it up
+        let mut supported: HashSet<Entity> = HashSet::new();
+        let mut stack: Vec<Entity> = glass_shards
+            .iter()
+            .filter(|&entity| holding(entity))
+            .filter(|&entity| shards.get(entity).is_ok_and(|(shard, _)| shard.on_frame))
+            .collect();
+        while let Some(entity) = stack.pop() {
+            if !supported.insert(entity) {
+                continue;
+            }
+            if let Ok((shard, _)) = shards.get(entity) {
+                stack.extend(
+                    shard
+                        .neighbors
+                        .iter()
+                        .copied()
+                        .filter(|&neighbor| holding(neighbor) && !supported.contains(&neighbor)),
+                );
+            }
+        }
+
+        for entity in glass_shards.iter() {
+            if holding(entity) && !supported.contains(&entity) {
+                commands
+                    .entity(entity)
+                    .insert((Unsupported, RigidBody::Dynamic));
+            }
+        }
+    }
+}